@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use anyhow::bail;
+use anyhow::Result;
+use clap::ArgMatches;
+use tokio::sync::mpsc;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+use crate::core::config_creator;
+use crate::core::config_manager;
+use crate::core::key_capture::AnyCapture;
+use crate::core::key_id::KeyId;
+use crate::core::keyboard_controller::KeyboardController;
+use crate::core::keymap::Keymap;
+use crate::core::utils;
+
+pub async fn keymap(args: &ArgMatches) -> Result<()> {
+    let keymap_path = utils::get_keymap_path(args)?;
+    let mut keymap = config_manager::read_keymap(&keymap_path)?;
+
+    match args.subcommand() {
+        Some(("edit", args)) => edit(args, &mut keymap).await?,
+        _ => bail!("Unknown keymap subcommand"),
+    }
+
+    config_manager::write_keymap(&keymap_path, &keymap)?;
+    Ok(())
+}
+
+async fn edit(args: &ArgMatches, keymap: &mut Keymap) -> Result<()> {
+    let key = args.get_one::<KeyId>("key").copied();
+    let led = args.get_one::<u32>("led").copied();
+    match (key, led, args.get_flag("unmap")) {
+        (Some(key), None, true) => match keymap.unmap_key(key) {
+            Some(led) => println!("{} is no longer mapped to LED {}", key, led),
+            None => bail!("{} is not in the keymap", key),
+        },
+        (None, Some(led), true) => {
+            keymap.unmap_led(led);
+            println!("LED {} is now marked as having no key", led);
+        }
+        (Some(key), Some(led), false) => {
+            keymap.map_key(key, led);
+            println!("LED {} is now mapped to {}", led, key);
+        }
+        (None, Some(led), false) => {
+            let keyboard_controller = KeyboardController::connect().await?;
+            let (mut sender, receiver) = mpsc::channel(100);
+            let keyboard_controller = Arc::new(Mutex::new(keyboard_controller));
+            KeyboardController::run(
+                keyboard_controller,
+                &TaskTracker::new(),
+                CancellationToken::new(),
+                receiver,
+            );
+            let mut capture = AnyCapture::from_args(args)?;
+            println!("Press the key of the LED that lights up. If it has no key, press LMB. To keep it as it is, press RMB");
+            config_creator::map_led(&mut capture, &mut sender, keymap, led).await?;
+        }
+        (Some(_), None, false) => bail!("Give the LED to map the key to with --led"),
+        (Some(_), Some(_), true) => bail!("Give either a key or an LED to unmap"),
+        (None, None, _) => bail!("Give a key with --key or an LED with --led"),
+    }
+    Ok(())
+}
@@ -1,15 +1,23 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::IsTerminal;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
 
-use anyhow::bail;
-use clap::{arg, command, value_parser, ArgMatches, Command};
+use anyhow::{bail, Context};
+use clap::{arg, command, value_parser, Arg, ArgAction, ArgMatches, Command};
 use tokio::sync::{mpsc, Mutex};
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 
+use crate::core::key_capture::AnyCapture;
+use crate::core::key_id::KeyId;
 use crate::core::keyboard_controller::KeyboardController;
-use crate::core::{config_creator, config_manager, utils};
+use crate::core::keyboard_diagram::{self, color_escape};
+use crate::core::{config_creator, config_manager, kle, utils};
 
+use super::keymap_subcommand;
 use super::module_subcommand;
 use super::start_subcommand;
 
@@ -21,6 +29,15 @@ pub(crate) fn parse_args() -> ArgMatches {
                 -c --config <FILE> "Sets a custom config file"
             )
             .required(false)
+            .global(true)
+            .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(
+                -k --keymap <FILE> "Sets a custom keymap file"
+            )
+            .required(false)
+            .global(true)
             .value_parser(value_parser!(PathBuf)),
         )
         .subcommands([
@@ -45,11 +62,57 @@ pub(crate) fn parse_args() -> ArgMatches {
                 )
                 .required(false)
                 .value_parser(value_parser!(u32))
+            ).arg(
+                arg!(
+                    -a --auto "Reads the keys from the LED names OpenRGB has, and only asks for the ones it can't find"
+                )
+            ).args(key_capture_args()),
+            Command::new("show").about("Draws the keyboard in the terminal, with the keys of each module in its own color. Doesn't need OpenRGB"),
+            Command::new("keymap").about("manage the keymap").subcommands([
+                Command::new("edit").about("Remaps or unmaps a single key or LED. With only an LED, it lights up and the key pressed is mapped to it")
+                    .arg(
+                        arg!(
+                            --key <KEY> "The key, like Q, F5, LeftShift or KP_Enter"
+                        )
+                        .required(false)
+                        .value_parser(|key: &str| KeyId::from_str(key))
+                    ).arg(
+                        arg!(
+                            --led <n> "The LED index"
+                        )
+                        .required(false)
+                        .value_parser(value_parser!(u32))
+                    ).arg(
+                        arg!(
+                            -u --unmap "Removes the key, or marks the LED as having no key"
+                        )
+                    ).args(key_capture_args()),
+            ]).subcommand_required(true),
+            Command::new("import-layout").about("Reads the position of each key from a keyboard-layout-editor.com JSON file into the keymap").arg(
+                arg!(
+                    <FILE> "The JSON file"
+                )
+                .value_parser(value_parser!(PathBuf))
             )
         ])
         .get_matches()
 }
 
+fn key_capture_args() -> [Arg; 2] {
+    [
+        arg!(
+            -e --evdev "Reads the keys from /dev/input instead of the terminal, so that keys like media keys can be mapped. Needs root or the input group"
+        ),
+        arg!(
+            -d --device <FILE> "The keyboard to read with --evdev, like /dev/input/event3. Can be given more than once. The other key devices of the same keyboard, like its media keys, are read too. Every device with keys is read by default. All of them are grabbed while reading"
+        )
+        .required(false)
+        .action(ArgAction::Append)
+        .requires("evdev")
+        .value_parser(value_parser!(PathBuf)),
+    ]
+}
+
 pub(crate) async fn main_command(matches: ArgMatches) -> anyhow::Result<()> {
     // TODO: Avoid unwrap
     match matches.subcommand_name() {
@@ -57,6 +120,9 @@ pub(crate) async fn main_command(matches: ArgMatches) -> anyhow::Result<()> {
         Some("create-config") => create_config(matches.subcommand().unwrap().1).await,
         Some("module") => module_subcommand::module(matches.subcommand().unwrap().1).await,
         Some("create-keymap") => create_keymap(matches.subcommand().unwrap().1).await,
+        Some("import-layout") => import_layout(matches.subcommand().unwrap().1),
+        Some("show") => show(matches.subcommand().unwrap().1),
+        Some("keymap") => keymap_subcommand::keymap(matches.subcommand().unwrap().1).await,
         _ => bail!("Unknown subcommand"),
     }
 }
@@ -76,6 +142,7 @@ async fn create_config(args: &ArgMatches) -> anyhow::Result<()> {
         keyboard_controller,
         &mut sender,
         args.get_one::<u32>("ledlimit").copied(),
+        &config_manager::keymap_progress_path(&utils::get_keymap_path(args)?),
     )
     .await?;
     config_manager::write_config_and_keymap_from_args(args, &new_config)?;
@@ -95,14 +162,90 @@ async fn create_keymap(args: &ArgMatches) -> anyhow::Result<()> {
         cancellation_token,
         receiver,
     );
-    let new_keymap = config_creator::create_keymap(
-        keyboard_controller,
-        &mut sender,
-        args.get_one::<u32>("ledlimit").copied(),
-    )
-    .await?;
+    let led_limit = args.get_one::<u32>("ledlimit").copied();
+    let mut capture = AnyCapture::from_args(args)?;
+    let progress_path = config_manager::keymap_progress_path(&keymap_path);
+    let new_keymap = if args.get_flag("auto") {
+        config_creator::create_keymap_auto(
+            keyboard_controller,
+            &mut sender,
+            led_limit,
+            &mut capture,
+            &progress_path,
+        )
+        .await?
+    } else {
+        config_creator::create_keymap(
+            keyboard_controller,
+            &mut sender,
+            led_limit,
+            &mut capture,
+            &progress_path,
+        )
+        .await?
+    };
 
     config_manager::write_keymap(&keymap_path, &new_keymap)?;
     println!("The keymap has been saved. You can now use the module command to configure modules");
     Ok(())
 }
+
+fn import_layout(args: &ArgMatches) -> anyhow::Result<()> {
+    let keymap_path = utils::get_keymap_path(args)?;
+    let mut keymap = config_manager::read_keymap(&keymap_path)?;
+    let layout_path = args.get_one::<PathBuf>("FILE").unwrap();
+    let contents = fs::read_to_string(layout_path)
+        .with_context(|| format!("Could not read {}", layout_path.display()))?;
+    let keys = kle::parse_kle(&contents)?;
+    let unmatched = kle::import_layout(&mut keymap, &keys);
+    config_manager::write_keymap(&keymap_path, &keymap)?;
+    println!(
+        "The positions of {} keys have been saved to the keymap",
+        keys.len() - unmatched.len()
+    );
+    if !unmatched.is_empty() {
+        println!(
+            "These keys were not found in the keymap: {}",
+            unmatched.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn show(args: &ArgMatches) -> anyhow::Result<()> {
+    let config = config_manager::read_config_and_keymap_from_args(args)?;
+    // The same colors the module subcommand lights the modules up with
+    let module_colors = utils::color_list(config.modules.len(), 100., 100.);
+    let mut colors = HashMap::new();
+    for (module, color) in config.modules.iter().zip(&module_colors) {
+        for led in module.module_leds.iter().flatten() {
+            colors.insert(*led, *color);
+        }
+    }
+    // Escapes would end up as garbage in files and pipes, where the colors can't be seen anyway
+    let ansi = std::io::stdout().is_terminal();
+    let diagram = keyboard_diagram::render_diagram(&config.keymap, &colors, ansi);
+    if diagram.is_empty() {
+        bail!("The keymap has no rows or key positions. Run the create-keymap subcommand to make a new one");
+    }
+    println!("{}", diagram);
+    if config.modules.is_empty() {
+        println!("There are no modules yet");
+    }
+    for (module, color) in config.modules.iter().zip(&module_colors) {
+        let leds = module.module_leds.iter().flatten();
+        let hidden = leds
+            .clone()
+            .filter(|led| config.keymap.led_position(**led).is_none())
+            .count();
+        if ansi {
+            print!("{}    \x1b[0m ", color_escape(*color));
+        }
+        print!("{} ({} LEDs", module.module_type.name(), leds.count());
+        if hidden > 0 {
+            print!(", {} of them not on the keyboard above", hidden);
+        }
+        println!(")");
+    }
+    Ok(())
+}
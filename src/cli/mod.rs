@@ -1,3 +1,4 @@
+pub mod keymap_subcommand;
 pub mod main_command;
 pub mod module_subcommand;
 pub mod start_subcommand;
@@ -12,14 +12,22 @@ use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 
 use crate::core::config_manager::{self, Configuration};
+use crate::core::key_id::KeyId;
 use crate::core::keyboard_controller::{KeyboardController, KeyboardControllerMessage};
 use crate::core::module::{Module, ModuleType};
 use crate::core::utils::{
     self, default_terminal_settings, highlight_all_modules, highlight_one_module,
     highlight_one_module_rainbow, prepare_terminal_event_capture,
 };
+use crate::modules::binding_mode::BindingModeModuleOptions;
+use crate::modules::keyboard_layout::KeyboardLayoutModuleOptions;
+use crate::modules::media_playing::MediaModuleOptions;
+use crate::modules::mic_mute::MicMuteModuleOptions;
 use crate::modules::noise::NoiseModuleOptions;
+use crate::modules::shortcut_hints::ShortcutHintsModuleOptions;
 use crate::modules::starfield::StarfieldModuleOptions;
+use crate::modules::volume::VolumeModuleOptions;
+use crate::modules::workspaces::WorkspacesModuleOptions;
 
 pub async fn module(args: &ArgMatches) -> Result<()> {
     let config_path = utils::get_config_path(args)?;
@@ -77,7 +85,7 @@ async fn add_module<'a>(
 
 async fn pick_leds(
     sender: &mut Sender<KeyboardControllerMessage>,
-    key_led_map: &HashMap<KeyCode, u32>,
+    key_led_map: &HashMap<KeyId, u32>,
 ) -> Result<Vec<Option<u32>>> {
     println!("Click the buttons which are in this module in order from left to right. Press LMB when done. Press RMB to add a button to the module which is not tied to any LED");
     prepare_terminal_event_capture()?;
@@ -94,7 +102,8 @@ async fn pick_leds(
                 panic!("Interrupted by user");
             }
             default_terminal_settings()?;
-            if let Some(&index_pressed) = key_led_map.get(&event.code) {
+            let key_led = KeyId::from_key_event(&event).and_then(|key| key_led_map.get(&key));
+            if let Some(&index_pressed) = key_led {
                 if module_leds.contains(&Some(index_pressed)) {
                     println!("This LED has already been added");
                     prepare_terminal_event_capture()?;
@@ -127,7 +136,8 @@ fn choose_module_type_to_add() -> Result<ModuleType> {
         .map(|module| format!("{} -- {}", module.name(), module.desc()))
         .collect();
 
-    Ok(all_module_types[utils::choose_option(&options)?])
+    let module_index = utils::choose_option(&options)?;
+    Ok(all_module_types[module_index].clone())
 }
 
 /// TODO make lock for this. If multiple processes run with this, it can lead to bad stuff
@@ -192,7 +202,9 @@ async fn choose_module_on_keyboard(
                 panic!("Interrupted by user");
             }
             default_terminal_settings()?;
-            if let Some(&index_pressed) = config.keymap.key_led_map.get(&event.code) {
+            let key_led =
+                KeyId::from_key_event(&event).and_then(|key| config.keymap.key_led_map.get(&key));
+            if let Some(&index_pressed) = key_led {
                 let module_index = get_modules_with_index(config, index_pressed);
                 if let Some(module_index) = module_index {
                     return Ok(module_index);
@@ -221,11 +233,9 @@ fn get_modules_with_index(config: &Configuration, led_index: u32) -> Option<usiz
             break;
         }
     }
-    let Some(module_index) = module_index else {
-        return None;
-    };
+    let module_index = module_index?;
     // TODO Make user select between each module that has this button
-    return Some(module_index);
+    Some(module_index)
 }
 
 async fn info(
@@ -282,10 +292,15 @@ async fn modify(
 
 fn reset_settings_to_default(module: &mut Module) {
     match module.module_type {
-        ModuleType::Workspaces => {}
-        ModuleType::Media => {}
+        ModuleType::Workspaces(ref mut opts) => *opts = WorkspacesModuleOptions::default(),
+        ModuleType::Media(ref mut opts) => *opts = MediaModuleOptions::default(),
         ModuleType::Starfield(ref mut opts) => *opts = StarfieldModuleOptions::default(),
         ModuleType::Noise(ref mut opts) => *opts = NoiseModuleOptions::default(),
+        ModuleType::BindingMode(ref mut opts) => *opts = BindingModeModuleOptions::default(),
+        ModuleType::ShortcutHints(ref mut opts) => *opts = ShortcutHintsModuleOptions::default(),
+        ModuleType::KeyboardLayout(ref mut opts) => *opts = KeyboardLayoutModuleOptions::default(),
+        ModuleType::Volume(ref mut opts) => *opts = VolumeModuleOptions::default(),
+        ModuleType::MicMute(ref mut opts) => *opts = MicMuteModuleOptions::default(),
     }
     println!("Reset settings to default")
 }
@@ -307,7 +322,7 @@ fn modify_settings(module: &mut Module) -> Result<()> {
 
 async fn modify_leds(
     sender: &mut Sender<KeyboardControllerMessage>,
-    key_led_map: &HashMap<KeyCode, u32>,
+    key_led_map: &HashMap<KeyId, u32>,
     module_to_modify: &mut Module,
 ) -> Result<()> {
     // Implement logic to modify LEDs of the module
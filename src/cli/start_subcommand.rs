@@ -29,6 +29,7 @@ pub(crate) async fn start(args: &ArgMatches) -> anyhow::Result<()> {
             cancellation_token.clone(),
             sender.clone(),
             module.module_leds.clone(),
+            &config.keymap,
         );
     }
 
@@ -0,0 +1,172 @@
+use std::future::Future;
+use std::process::Stdio;
+
+use anyhow::{bail, Context};
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdout, Command};
+
+/// The default output or input of PulseAudio. PipeWire works too through pipewire-pulse
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum AudioDevice {
+    Sink,
+    /// Like a microphone
+    Source,
+}
+
+impl AudioDevice {
+    fn pactl_name(&self) -> &'static str {
+        match self {
+            AudioDevice::Sink => "sink",
+            AudioDevice::Source => "source",
+        }
+    }
+
+    fn default_name(&self) -> &'static str {
+        match self {
+            AudioDevice::Sink => "@DEFAULT_SINK@",
+            AudioDevice::Source => "@DEFAULT_SOURCE@",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct AudioState {
+    /// 1.0 is 100%. Can go above that
+    pub(crate) volume: f32,
+    pub(crate) muted: bool,
+}
+
+/// Somewhere to get the volume and mute state of an audio device from
+pub(crate) trait AudioSource {
+    fn get_state(&mut self) -> impl Future<Output = anyhow::Result<AudioState>> + Send;
+
+    /// Waits until something happens that can change the state
+    fn wait_for_change(&mut self) -> impl Future<Output = anyhow::Result<()>> + Send;
+}
+
+/// Reads the state with `pactl` and listens for changes with `pactl subscribe`
+pub(crate) struct PactlAudio {
+    device: AudioDevice,
+    events: Lines<BufReader<ChildStdout>>,
+    // Killed when dropped
+    _subscription: Child,
+}
+
+impl PactlAudio {
+    pub(crate) fn new(device: AudioDevice) -> anyhow::Result<Self> {
+        let mut subscription = Command::new("pactl")
+            .arg("subscribe")
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to run pactl subscribe. Is pactl installed?")?;
+        let stdout = subscription
+            .stdout
+            .take()
+            .context("pactl subscribe has no stdout")?;
+        Ok(Self {
+            device,
+            events: BufReader::new(stdout).lines(),
+            _subscription: subscription,
+        })
+    }
+
+    async fn pactl(&self, command: &str) -> anyhow::Result<String> {
+        let output = Command::new("pactl")
+            .arg(format!("get-{}-{}", self.device.pactl_name(), command))
+            .arg(self.device.default_name())
+            .output()
+            .await?;
+        if !output.status.success() {
+            bail!(
+                "pactl get-{}-{} failed. {}",
+                self.device.pactl_name(),
+                command,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+impl AudioSource for PactlAudio {
+    async fn get_state(&mut self) -> anyhow::Result<AudioState> {
+        let volume = self.pactl("volume").await?;
+        let mute = self.pactl("mute").await?;
+        Ok(AudioState {
+            volume: parse_volume(&volume)
+                .with_context(|| format!("Invalid volume from pactl: {}", volume))?,
+            muted: parse_mute(&mute)
+                .with_context(|| format!("Invalid mute state from pactl: {}", mute))?,
+        })
+    }
+
+    /// Waits for a change of any device of our type, or of the server, which is where changes
+    /// of the default device show up
+    async fn wait_for_change(&mut self) -> anyhow::Result<()> {
+        let device_event = format!("on {} #", self.device.pactl_name());
+        loop {
+            let Some(line) = self.events.next_line().await? else {
+                bail!("pactl subscribe exited");
+            };
+            if line.contains(&device_event) || line.contains("on server") {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Parses the first channel of `pactl get-sink-volume`, like
+/// "Volume: front-left: 32768 /  50% / -18.06 dB,   front-right: ..."
+fn parse_volume(output: &str) -> Option<f32> {
+    let percent = output.split('/').nth(1)?.trim().strip_suffix('%')?;
+    percent.parse::<f32>().ok().map(|percent| percent / 100.)
+}
+
+/// Parses `pactl get-sink-mute`, like "Mute: no"
+fn parse_mute(output: &str) -> Option<bool> {
+    match output.trim().strip_prefix("Mute:")?.trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+impl AudioSource for super::scripted::Scripted<AudioState> {
+    async fn get_state(&mut self) -> anyhow::Result<AudioState> {
+        Ok(*self.current())
+    }
+
+    async fn wait_for_change(&mut self) -> anyhow::Result<()> {
+        self.next().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_mute, parse_volume};
+
+    #[test]
+    fn test_parse_volume() {
+        assert_eq!(
+            parse_volume(
+                "Volume: front-left: 32768 /  50% / -18.06 dB,   front-right: 32768 /  50% / -18.06 dB\n        balance 0.00\n"
+            ),
+            Some(0.5)
+        );
+        assert_eq!(
+            parse_volume("Volume: mono: 98304 / 150% / 10.57 dB\n"),
+            Some(1.5)
+        );
+        assert_eq!(parse_volume("No such entity"), None);
+    }
+
+    #[test]
+    fn test_parse_mute() {
+        assert_eq!(parse_mute("Mute: yes\n"), Some(true));
+        assert_eq!(parse_mute("Mute: no\n"), Some(false));
+        assert_eq!(parse_mute("Mute: maybe"), None);
+    }
+}
@@ -0,0 +1,176 @@
+use super::key_id::KeyId;
+use super::keymap::{Keymap, LedPosition};
+use super::kle::key_id_from_legend;
+
+/// The LEDs of an OpenRGB zone, and where they are if the zone is a matrix
+#[derive(Debug)]
+pub(crate) struct ZoneLayout {
+    pub(crate) leds_count: u32,
+    /// Rows of LED indexes within the zone, with None where there is no LED
+    pub(crate) matrix: Option<Vec<Vec<Option<u32>>>>,
+}
+
+/// A keymap made from what OpenRGB knows about the keyboard
+#[derive(Debug)]
+pub(crate) struct AutoKeymap {
+    pub(crate) keymap: Keymap,
+    /// LEDs that are named like keys, but couldn't be matched to a key. These have to be pressed
+    /// by hand
+    pub(crate) unresolved: Vec<u32>,
+}
+
+/// Builds the keymap from LED names like "Key: Left Shift", and the rows and geometry from the
+/// matrices of the zones. LEDs that aren't keys, like logos and light bars, are skipped
+pub(crate) fn keymap_from_metadata(led_names: &[String], zones: &[ZoneLayout]) -> AutoKeymap {
+    let mut keymap = Keymap::default();
+    let mut unresolved = Vec::new();
+    for (led, name) in led_names.iter().enumerate() {
+        let led = led as u32;
+        let Some(key) = name.strip_prefix("Key: ") else {
+            keymap.skip_indicies.insert(led);
+            continue;
+        };
+        match key_id_from_led_name(key) {
+            // Only one LED can be a key, so the ones after it are left for the user
+            Some(code) if !keymap.key_led_map.contains_key(&code) => {
+                keymap.key_led_map.insert(code, led);
+            }
+            _ => unresolved.push(led),
+        }
+    }
+
+    // Zone matrices are numbered from the first LED of the zone
+    let mut zone_start = 0;
+    let mut y = 0;
+    for zone in zones {
+        if let Some(matrix) = &zone.matrix {
+            for row in matrix {
+                let row_leds = row
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(x, led)| Some((x, zone_start + (*led)?)))
+                    .filter(|&(_, led)| (led as usize) < led_names.len())
+                    .collect::<Vec<_>>();
+                if let Some(&(_, first)) = row_leds.first() {
+                    keymap.first_in_row.push(first);
+                }
+                for (x, led) in row_leds {
+                    keymap.geometry.insert(
+                        led,
+                        LedPosition {
+                            x: x as f32,
+                            y: y as f32,
+                            width: 1.,
+                        },
+                    );
+                }
+                y += 1;
+            }
+        }
+        zone_start += zone.leds_count;
+    }
+    AutoKeymap { keymap, unresolved }
+}
+
+/// The key of an OpenRGB LED name without the "Key: " prefix, like "Left Shift" or "\\ (ISO)"
+fn key_id_from_led_name(name: &str) -> Option<KeyId> {
+    if name == "\\ (ISO)" {
+        return Some(KeyId::IsoBackslash);
+    }
+    let name = name
+        .trim_end_matches(" (ANSI)")
+        .trim_end_matches(" (ISO)")
+        .trim();
+    if let Some(key) = name.strip_prefix("Number Pad ") {
+        return match key {
+            "Enter" => Some(KeyId::KeypadEnter),
+            _ => {
+                let mut chars = key.chars();
+                let (Some(char), None) = (chars.next(), chars.next()) else {
+                    return None;
+                };
+                Some(KeyId::Keypad(char))
+            }
+        };
+    }
+    Some(match name {
+        "Left Shift" => KeyId::LeftShift,
+        "Right Shift" => KeyId::RightShift,
+        "Left Control" => KeyId::LeftCtrl,
+        "Right Control" => KeyId::RightCtrl,
+        "Left Alt" => KeyId::LeftAlt,
+        "Right Alt" => KeyId::RightAlt,
+        "Left Windows" | "Left Super" => KeyId::LeftSuper,
+        "Right Windows" | "Right Super" => KeyId::RightSuper,
+        "Left Fn" | "Right Fn" => KeyId::Fn,
+        "Up Arrow" => KeyId::Up,
+        "Down Arrow" => KeyId::Down,
+        "Left Arrow" => KeyId::Left,
+        "Right Arrow" => KeyId::Right,
+        "Pause/Break" => KeyId::Pause,
+        "Media Play/Pause" => KeyId::MediaPlayPause,
+        "Media Stop" => KeyId::MediaStop,
+        "Media Next" => KeyId::MediaNext,
+        "Media Previous" => KeyId::MediaPrevious,
+        "Media Mute" => KeyId::Mute,
+        "Media Volume +" => KeyId::VolumeUp,
+        "Media Volume -" => KeyId::VolumeDown,
+        _ => return key_id_from_legend(name, 0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{keymap_from_metadata, ZoneLayout};
+    use crate::core::key_id::KeyId;
+    use crate::core::keymap::LedPosition;
+
+    #[test]
+    fn test_keymap_from_metadata() {
+        let led_names = [
+            "Logo",
+            "Key: Escape",
+            "Key: A",
+            "Key: Left Shift",
+            "Key: \\ (ANSI)",
+            "Key: \\ (ISO)",
+            "Key: Number Pad 1",
+            "Key: Escape",
+        ]
+        .map(str::to_owned);
+        let zones = [
+            ZoneLayout {
+                leds_count: 1,
+                matrix: None,
+            },
+            ZoneLayout {
+                leds_count: 7,
+                matrix: Some(vec![
+                    vec![Some(0), None, Some(5), Some(6)],
+                    vec![None, Some(1), Some(2), Some(3)],
+                ]),
+            },
+        ];
+        let auto = keymap_from_metadata(&led_names, &zones);
+        let keymap = auto.keymap;
+        assert_eq!(keymap.key_led_map[&KeyId::Esc], 1);
+        assert_eq!(keymap.key_led_map[&KeyId::Char('a')], 2);
+        assert_eq!(keymap.key_led_map[&KeyId::LeftShift], 3);
+        assert_eq!(keymap.key_led_map[&KeyId::Char('\\')], 4);
+        assert_eq!(keymap.key_led_map[&KeyId::IsoBackslash], 5);
+        assert_eq!(keymap.key_led_map[&KeyId::Keypad('1')], 6);
+        assert_eq!(keymap.key_led_map.len(), 6);
+        assert_eq!(auto.unresolved, vec![7]);
+        assert_eq!(keymap.skip_indicies, [0].into());
+        assert_eq!(keymap.first_in_row, vec![1, 2]);
+        assert_eq!(
+            keymap.geometry[&4],
+            LedPosition {
+                x: 3.,
+                y: 1.,
+                width: 1.,
+            }
+        );
+        assert_eq!(keymap.geometry[&7].x, 3.);
+    }
+}
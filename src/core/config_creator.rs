@@ -1,138 +1,380 @@
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use crossterm::event::*;
+use anyhow::bail;
 use openrgb::data::Color;
 use tokio::sync::mpsc::Sender;
 
 use crate::cli::module_subcommand;
-use crate::core::config_manager::Configuration;
+use crate::core::config_manager::{Configuration, KeymapProgress};
+use crate::core::key_capture::{CaptureEvent, KeyCapture, TerminalCapture};
 use crate::core::keymap::Keymap;
-use crate::core::utils::default_terminal_settings;
+use crate::core::{auto_keymap, config_manager, utils};
 
 use super::keyboard_controller::{KeyboardController, KeyboardControllerMessage};
-use super::utils::prepare_terminal_event_capture;
+
+const WALK_INSTRUCTIONS: &str = "Press every key as it lights up. If no key lights up, press LMB. If no reaction is given when key is pressed, press RMB. If the wrong key was pressed, press Ctrl+Z to go back";
 
 pub(crate) async fn start_config_creator(
     keyboard_controller: Arc<Mutex<KeyboardController>>,
     sender: &mut Sender<KeyboardControllerMessage>,
     led_limit: Option<u32>,
+    progress_path: &Path,
 ) -> anyhow::Result<Configuration> {
-    let mut config = Configuration::default();
-    println!("Press every key as it lights up. If no key lights up, press LMB. If no reaction is given when key is pressed, press RMB");
-    build_key_led_map(keyboard_controller, sender, &mut config.keymap, led_limit).await?;
-    println!(
-        "Press the first key of every row. In order. When all of them have been pressed, press LMB"
-    );
-    build_first_in_row(sender, &mut config.keymap).await?;
+    let keymap = create_keymap(
+        keyboard_controller,
+        sender,
+        led_limit,
+        &mut TerminalCapture,
+        progress_path,
+    )
+    .await?;
+    let mut config = Configuration {
+        keymap,
+        ..Default::default()
+    };
     println!("Now, we're going to place the modules");
     module_subcommand::add(sender, &mut config).await?;
     Ok(config)
 }
 
 async fn build_first_in_row(
+    capture: &mut impl KeyCapture,
     sender: &mut Sender<KeyboardControllerMessage>,
     keymap: &mut Keymap,
 ) -> anyhow::Result<()> {
-    prepare_terminal_event_capture()?;
+    capture.start()?;
     KeyboardController::turn_all_off(sender).await?;
     loop {
-        let event = read().unwrap();
-        if let Event::Key(event) = event {
-            if event.kind != KeyEventKind::Press {
-                continue;
-            }
-            if event.modifiers.intersects(KeyModifiers::CONTROL) && event.code == KeyCode::Char('c')
-            {
-                default_terminal_settings()?;
-                panic!("Interrupted by user");
-            }
-            if let Some(&index_pressed) = keymap.key_led_map.get(&event.code) {
-                keymap.first_in_row.push(index_pressed);
-                KeyboardController::update_led(sender, index_pressed, Color::new(255, 255, 255))
+        match capture.next_event().await? {
+            CaptureEvent::Key(key) => {
+                let key_led = key.and_then(|key| keymap.key_led_map.get(&key));
+                if let Some(&index_pressed) = key_led {
+                    keymap.first_in_row.push(index_pressed);
+                    KeyboardController::update_led(
+                        sender,
+                        index_pressed,
+                        Color::new(255, 255, 255),
+                    )
                     .await?;
-            } else {
-                default_terminal_settings()?;
-                println!("This button was not pressed in the last stage, it can't be marked as first button in row");
-                prepare_terminal_event_capture()?;
+                } else {
+                    capture.stop()?;
+                    println!("This button was not pressed in the last stage, it can't be marked as first button in row");
+                    capture.start()?;
+                }
             }
-        }
-        if let Event::Mouse(event) = event {
-            if event.kind == MouseEventKind::Down(MouseButton::Left) {
-                default_terminal_settings()?;
+            CaptureEvent::Undo => {
+                if let Some(led) = keymap.first_in_row.pop() {
+                    KeyboardController::update_led(sender, led, Color::new(0, 0, 0)).await?;
+                }
+            }
+            CaptureEvent::LeftClick => {
+                capture.stop()?;
                 println!("Great. There are {} rows", keymap.first_in_row.len());
                 KeyboardController::turn_all_off(sender).await?;
                 break;
             }
+            CaptureEvent::OtherClick => {}
+            CaptureEvent::Interrupt => {
+                capture.stop()?;
+                panic!("Interrupted by user");
+            }
         }
     }
-    default_terminal_settings()?;
+    capture.stop()?;
     Ok(())
 }
 
-async fn build_key_led_map(
-    keyboard_controller: Arc<Mutex<KeyboardController>>,
-    sender: &mut Sender<KeyboardControllerMessage>,
-    keymap: &mut Keymap,
+async fn led_indices(
+    keyboard_controller: &Arc<Mutex<KeyboardController>>,
     led_limit: Option<u32>,
-) -> anyhow::Result<()> {
-    prepare_terminal_event_capture()?;
-    KeyboardController::turn_all_off(sender).await?;
-    for index in 0..keyboard_controller
+) -> Vec<u32> {
+    (0..keyboard_controller
         .lock()
         .await
         .num_leds()
-        .min(led_limit.unwrap_or(u32::MAX))
-    {
-        if index != 0 {
-            KeyboardController::update_led(sender, index - 1, Color::new(0, 0, 0)).await?;
+        .min(led_limit.unwrap_or(u32::MAX)))
+        .collect()
+}
+
+/// Lights up each LED of `progress` in turn and maps it to the key that is pressed. The progress
+/// is saved after every key. A key that is already mapped to another LED is refused, so that an
+/// undo never has to bring back a mapping. So is a key that can't be told apart from others
+async fn build_key_led_map(
+    capture: &mut impl KeyCapture,
+    sender: &mut Sender<KeyboardControllerMessage>,
+    progress: &mut KeymapProgress,
+    progress_path: &Path,
+) -> anyhow::Result<()> {
+    capture.start()?;
+    KeyboardController::turn_all_off(sender).await?;
+    let mut last_index = None;
+    while let Some(&index) = progress.leds.get(progress.next) {
+        if let Some(last_index) = last_index {
+            KeyboardController::update_led(sender, last_index, Color::new(0, 0, 0)).await?;
         }
+        last_index = Some(index);
         KeyboardController::update_led(sender, index, Color::new(255, 255, 255)).await?;
-        loop {
-            let event = read().unwrap();
-            match event {
-                Event::Key(event) => {
-                    if event.kind != KeyEventKind::Press {
-                        continue;
-                    }
-                    if event.modifiers.intersects(KeyModifiers::CONTROL)
-                        && event.code == KeyCode::Char('c')
-                    {
-                        default_terminal_settings()?;
-                        panic!("Interrupted by user");
-                    }
-                    keymap.key_led_map.insert(event.code, index);
-                    break;
+        match capture.next_event().await? {
+            CaptureEvent::Key(Some(key)) => {
+                let mapped_led = progress.keymap.key_led_map.get(&key).copied();
+                if let Some(mapped_led) = mapped_led.filter(|&led| led != index) {
+                    capture.stop()?;
+                    println!(
+                        "{} is already mapped to LED {}. Press another key, or Ctrl+Z to go back",
+                        key, mapped_led
+                    );
+                    capture.start()?;
+                    continue;
                 }
-
-                Event::Mouse(event) => match event.kind {
-                    MouseEventKind::Down(mouse_button) => {
-                        if mouse_button == MouseButton::Left {
-                            keymap.skip_indicies.insert(index);
-                        }
-                        break;
-                    }
-                    _ => continue,
-                },
-                _ => continue,
+                progress.keymap.map_key(key, index)
+            }
+            CaptureEvent::Key(None) => {
+                capture.stop()?;
+                println!("This key can't be told apart from others. Press another key, or left click if it has none");
+                capture.start()?;
+                continue;
+            }
+            CaptureEvent::LeftClick => progress.keymap.unmap_led(index),
+            CaptureEvent::OtherClick => {}
+            CaptureEvent::Undo => {
+                if progress.next > 0 {
+                    progress.next -= 1;
+                    progress.keymap.forget_led(progress.leds[progress.next]);
+                    config_manager::write_keymap_progress(progress_path, progress)?;
+                }
+                continue;
+            }
+            CaptureEvent::Interrupt => {
+                capture.stop()?;
+                panic!("Interrupted by user");
             }
         }
+        progress.next += 1;
+        config_manager::write_keymap_progress(progress_path, progress)?;
     }
-    default_terminal_settings()?;
+    capture.stop()?;
     Ok(())
 }
 
+/// The progress of an earlier keymap that was interrupted, if the user wants to continue it
+fn resume_progress(progress_path: &Path) -> anyhow::Result<Option<KeymapProgress>> {
+    let Some(progress) = config_manager::read_keymap_progress(progress_path)? else {
+        return Ok(None);
+    };
+    let resume = utils::confirm_action(
+        &format!(
+            "An earlier keymap was stopped after {} of {} keys. Continue it? [Y/n] ",
+            progress.next,
+            progress.leds.len()
+        ),
+        true,
+    )?;
+    Ok(resume.then_some(progress))
+}
+
 pub(crate) async fn create_keymap(
     keyboard_controller: Arc<Mutex<KeyboardController>>,
     sender: &mut Sender<KeyboardControllerMessage>,
     led_limit: Option<u32>,
+    capture: &mut impl KeyCapture,
+    progress_path: &Path,
 ) -> anyhow::Result<Keymap> {
-    let mut keymap = Keymap::default();
-    println!("Press every key as it lights up. If no key lights up, press LMB. If no reaction is given when key is pressed, press RMB");
-    build_key_led_map(keyboard_controller, sender, &mut keymap, led_limit).await?;
+    let mut progress = match resume_progress(progress_path)? {
+        Some(progress) => progress,
+        None => KeymapProgress {
+            leds: led_indices(&keyboard_controller, led_limit).await,
+            ..Default::default()
+        },
+    };
+    println!("{}", WALK_INSTRUCTIONS);
+    build_key_led_map(capture, sender, &mut progress, progress_path).await?;
     println!(
         "Press the first key of every row. In order. When all of them have been pressed, press LMB"
     );
-    build_first_in_row(sender, &mut keymap).await?;
-    Ok(keymap)
+    build_first_in_row(capture, sender, &mut progress.keymap).await?;
+    config_manager::remove_keymap_progress(progress_path)?;
+    Ok(progress.keymap)
+}
+
+/// Builds the keymap from the LED names and zone matrices OpenRGB has for the keyboard. Only the
+/// keys that can't be worked out from those are asked for
+pub(crate) async fn create_keymap_auto(
+    keyboard_controller: Arc<Mutex<KeyboardController>>,
+    sender: &mut Sender<KeyboardControllerMessage>,
+    led_limit: Option<u32>,
+    capture: &mut impl KeyCapture,
+    progress_path: &Path,
+) -> anyhow::Result<Keymap> {
+    let mut progress = match resume_progress(progress_path)? {
+        Some(progress) => progress,
+        None => {
+            let lock = keyboard_controller.lock().await;
+            let mut led_names = lock.led_names();
+            let zones = lock.zone_layouts();
+            drop(lock);
+            led_names.truncate(led_limit.unwrap_or(u32::MAX) as usize);
+
+            let auto = auto_keymap::keymap_from_metadata(&led_names, &zones);
+            println!(
+                "Found {} keys in the LED names of the keyboard",
+                auto.keymap.key_led_map.len()
+            );
+            KeymapProgress {
+                keymap: auto.keymap,
+                leds: auto.unresolved,
+                next: 0,
+            }
+        }
+    };
+    if progress.next < progress.leds.len() {
+        println!(
+            "{} keys are left. {}",
+            progress.leds.len() - progress.next,
+            WALK_INSTRUCTIONS
+        );
+        build_key_led_map(capture, sender, &mut progress, progress_path).await?;
+    }
+    if progress.keymap.first_in_row.is_empty() {
+        println!(
+            "The keyboard has no matrix to find the rows in. Press the first key of every row. In order. When all of them have been pressed, press LMB"
+        );
+        build_first_in_row(capture, sender, &mut progress.keymap).await?;
+    }
+    config_manager::remove_keymap_progress(progress_path)?;
+    Ok(progress.keymap)
+}
+
+/// Lights up the LED and maps it to the key that is pressed. LMB marks it as an LED without a key
+pub(crate) async fn map_led(
+    capture: &mut impl KeyCapture,
+    sender: &mut Sender<KeyboardControllerMessage>,
+    keymap: &mut Keymap,
+    led: u32,
+) -> anyhow::Result<()> {
+    capture.start()?;
+    KeyboardController::turn_all_off(sender).await?;
+    KeyboardController::update_led(sender, led, Color::new(255, 255, 255)).await?;
+    let event = loop {
+        match capture.next_event().await? {
+            CaptureEvent::Undo => continue,
+            event => break event,
+        }
+    };
+    capture.stop()?;
+    KeyboardController::turn_all_off(sender).await?;
+    match event {
+        CaptureEvent::Key(Some(key)) => {
+            keymap.map_key(key, led);
+            println!("LED {} is now mapped to {}", led, key);
+        }
+        CaptureEvent::Key(None) => bail!("This key can't be told apart from others"),
+        CaptureEvent::LeftClick => {
+            keymap.unmap_led(led);
+            println!("LED {} is now marked as having no key", led);
+        }
+        CaptureEvent::Interrupt => bail!("Interrupted by user"),
+        CaptureEvent::OtherClick | CaptureEvent::Undo => println!("Nothing was changed"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, VecDeque};
+
+    use anyhow::Result;
+    use evdev::{EventType, InputEvent, Key};
+    use tokio::sync::mpsc;
+    use tokio_util::sync::CancellationToken;
+
+    use super::build_key_led_map;
+    use crate::core::config_manager::KeymapProgress;
+    use crate::core::key_capture::{CaptureEvent, EvdevCapture, KeyCapture};
+    use crate::core::key_id::KeyId;
+
+    /// Hands out the same events as the terminal would, including keys it can't tell apart
+    struct ScriptedCapture(VecDeque<CaptureEvent>);
+
+    impl KeyCapture for ScriptedCapture {
+        async fn next_event(&mut self) -> Result<CaptureEvent> {
+            Ok(self.0.pop_front().expect("Ran out of events"))
+        }
+    }
+
+    fn progress_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "keyboard-indicators-test-{}-{}.progress.yaml",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_build_key_led_map() {
+        let (event_sender, events) = mpsc::channel(100);
+        let mut capture = EvdevCapture::from_events(events, CancellationToken::new());
+        let press = |key: Key, value| InputEvent::new(EventType::KEY, key.code(), value);
+        for event in [
+            press(Key::KEY_A, 1),
+            // A is mapped to the first LED already, so it is refused for the second
+            press(Key::KEY_A, 1),
+            press(Key::KEY_B, 1),
+            // Going back to the second LED forgets only B
+            press(Key::KEY_LEFTCTRL, 1),
+            press(Key::KEY_Z, 1),
+            press(Key::KEY_LEFTCTRL, 0),
+            press(Key::KEY_C, 1),
+            press(Key::KEY_D, 1),
+        ] {
+            event_sender.send(event).await.unwrap();
+        }
+        let (mut sender, _receiver) = mpsc::channel(100);
+        let mut progress = KeymapProgress {
+            leds: vec![0, 1, 2],
+            ..Default::default()
+        };
+        let progress_path = progress_path("map");
+        build_key_led_map(&mut capture, &mut sender, &mut progress, &progress_path)
+            .await
+            .unwrap();
+        std::fs::remove_file(&progress_path).unwrap();
+        assert_eq!(
+            progress.keymap.key_led_map,
+            HashMap::from([
+                (KeyId::Char('a'), 0),
+                (KeyId::Char('c'), 1),
+                (KeyId::Char('d'), 2),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_key_that_cant_be_told_apart() {
+        let mut capture = ScriptedCapture(VecDeque::from([
+            // Neither recorded nor skipped, the first LED still waits for its key
+            CaptureEvent::Key(None),
+            CaptureEvent::Key(Some(KeyId::Char('a'))),
+            CaptureEvent::Key(None),
+            CaptureEvent::LeftClick,
+        ]));
+        let (mut sender, _receiver) = mpsc::channel(100);
+        let mut progress = KeymapProgress {
+            leds: vec![0, 1],
+            ..Default::default()
+        };
+        let progress_path = progress_path("unknown");
+        build_key_led_map(&mut capture, &mut sender, &mut progress, &progress_path)
+            .await
+            .unwrap();
+        std::fs::remove_file(&progress_path).unwrap();
+        assert!(capture.0.is_empty());
+        assert_eq!(progress.next, 2);
+        assert_eq!(
+            progress.keymap.key_led_map,
+            HashMap::from([(KeyId::Char('a'), 0)])
+        );
+        assert_eq!(progress.keymap.skip_indicies, [1].into());
+    }
 }
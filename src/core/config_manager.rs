@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{fs, io};
 
 use anyhow::{bail, Context};
@@ -23,6 +23,17 @@ pub(crate) struct Configuration {
     pub(crate) keymap_path: PathBuf,
 }
 
+/// How far the walk over the LEDs of create-keymap got. It is saved after every key, so an
+/// interrupted walk can be continued
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub(crate) struct KeymapProgress {
+    pub(crate) keymap: Keymap,
+    /// The LEDs that are walked over, in order
+    pub(crate) leds: Vec<u32>,
+    /// The index in `leds` of the next LED
+    pub(crate) next: usize,
+}
+
 /// Returns None if the file is not found, an error in the case of another error, and the
 /// deserialized config object in case the files are found
 pub(crate) fn read_config_and_keymap(
@@ -44,15 +55,20 @@ pub(crate) fn read_config_and_keymap(
             serde_yaml::from_str::<Configuration>(&contents).context("Error reading the config")?;
     }
 
-    let contents = fs::read_to_string(&keymap_path)
-        .context("There is no keymap file. Run the create-keymap subcommand to construct one.")?;
-    let keymap = serde_yaml::from_str::<Keymap>(&contents)?;
-    config.keymap = keymap;
+    config.keymap = read_keymap(&keymap_path)?;
     config.config_path = config_path;
     config.keymap_path = keymap_path;
     Ok(config)
 }
 
+pub(crate) fn read_keymap(keymap_path: &PathBuf) -> anyhow::Result<Keymap> {
+    let contents = fs::read_to_string(keymap_path)
+        .context("There is no keymap file. Run the create-keymap subcommand to construct one.")?;
+    // Keys named the way crossterm does are still read, and are only renamed when the keymap is
+    // saved. Reading never writes, so keymaps in read-only places keep working
+    Ok(serde_yaml::from_str::<Keymap>(&contents)?)
+}
+
 pub(crate) fn read_config_and_keymap_from_args(args: &ArgMatches) -> anyhow::Result<Configuration> {
     let config_path = utils::get_config_path(args)?;
     let keymap_path = utils::get_keymap_path(args)?;
@@ -71,6 +87,38 @@ pub(crate) fn write_keymap(path: &PathBuf, keymap: &Keymap) -> anyhow::Result<()
     Ok(())
 }
 
+/// Where the progress of making the keymap at `keymap_path` is kept
+pub(crate) fn keymap_progress_path(keymap_path: &Path) -> PathBuf {
+    keymap_path.with_extension("progress.yaml")
+}
+
+/// Returns None if there is no progress to continue
+pub(crate) fn read_keymap_progress(path: &Path) -> anyhow::Result<Option<KeymapProgress>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(Some(serde_yaml::from_str(&contents).with_context(
+        || format!("Could not read the keymap progress in {}", path.display()),
+    )?))
+}
+
+pub(crate) fn write_keymap_progress(path: &Path, progress: &KeymapProgress) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_yaml::to_string(progress)?)?;
+    Ok(())
+}
+
+pub(crate) fn remove_keymap_progress(path: &Path) -> anyhow::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
 pub(crate) fn write_config_and_keymap(
     config_path: &PathBuf,
     keymap_path: &PathBuf,
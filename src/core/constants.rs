@@ -1,11 +1,17 @@
 use openrgb::data::Color;
 use rgb::RGBA8;
+use std::time::Duration;
 
 pub const CURRENT_WORKSPACE_OVERLAY: RGBA8 = RGBA8::new(0, 0, 0, 200);
 pub const UNFOCUSED_WORKSPACE_OVERLAY: RGBA8 = RGBA8::new(0, 0, 0, 240);
+/// Used for workspaces that are visible on an output other than the focused one
+pub const VISIBLE_WORKSPACE_OVERLAY: RGBA8 = RGBA8::new(0, 0, 0, 220);
 pub const UNFOCUSED_DEFAULT_WORKSPACE_COLOR: Color = Color::new(128, 128, 128);
+/// Brightness of a workspace without windows when the window count is shown as brightness
+pub const MIN_WINDOW_COUNT_BRIGHTNESS: f32 = 0.25;
 pub const EMPTY_WORKSPACE_COLOR: Color = Color::new(0, 0, 0);
 pub const URGENT_WORKSPACE_COLOR: Color = Color::new(255, 0, 0);
+pub const URGENT_ANIMATION_FRAME_TIME: Duration = Duration::from_millis(20);
 pub const SPOTIFY_WORKSPACE_COLOR: Color = Color::new(30, 215, 96);
 pub const DISCORD_WORKSPACE_COLOR: Color = Color::new(88, 101, 242);
 pub const FIREFOX_WORKSPACE_COLOR: Color = Color::new(230, 0x60, 0);
@@ -14,3 +20,24 @@ pub const SPOTIFY_MEDIA_PLAYING_COLOR: Color = Color::new(30, 215, 96);
 pub const NETFLIX_MEDIA_PLAYING_COLOR: Color = Color::new(229, 9, 20);
 pub const DEFAULT_MEDIA_PLAYING_COLOR: Color = Color::new(255, 0, 0);
 pub const PAUSED_MEDIA_PLAYING_COLOR: Color = Color::new(30, 30, 30);
+pub const MEDIA_STATUS_COLOR: Color = Color::new(200, 200, 200);
+pub const MEDIA_LOOP_TRACK_COLOR: Color = Color::new(255, 160, 0);
+
+pub const DEFAULT_KEYBOARD_LAYOUT_COLOR: Color = Color::new(0, 80, 255);
+pub const DEFAULT_BINDING_MODE_COLOR: Color = Color::new(255, 120, 0);
+
+pub const WORKSPACE_SHORTCUT_COLOR: Color = Color::new(0, 120, 255);
+pub const EXEC_SHORTCUT_COLOR: Color = Color::new(0, 255, 80);
+pub const FOCUS_SHORTCUT_COLOR: Color = Color::new(255, 200, 0);
+pub const MOVE_SHORTCUT_COLOR: Color = Color::new(255, 0, 200);
+pub const OTHER_SHORTCUT_COLOR: Color = Color::new(255, 255, 255);
+
+pub const DEFAULT_VOLUME_COLOR: Color = Color::new(0, 200, 255);
+pub const DEFAULT_MUTED_COLOR: Color = Color::new(255, 0, 0);
+pub const VOLUME_ANIMATION_FRAME_TIME: Duration = Duration::from_millis(20);
+
+pub const DEFAULT_MIC_MUTED_COLOR: Color = Color::new(255, 0, 0);
+pub const DEFAULT_MIC_LIVE_COLOR: Color = Color::new(0, 0, 0);
+
+pub const SWAY_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+pub const SWAY_MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
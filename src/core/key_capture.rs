@@ -0,0 +1,506 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind};
+use evdev::{Device, InputEvent, InputEventKind, Key};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use super::key_id::KeyId;
+use super::utils::{default_terminal_settings, prepare_terminal_event_capture};
+
+/// What the user did while keys were being captured
+#[derive(Debug, PartialEq)]
+pub(crate) enum CaptureEvent {
+    /// A key was pressed. None if it can't be told apart from other keys
+    Key(Option<KeyId>),
+    LeftClick,
+    /// The right or middle mouse button
+    OtherClick,
+    /// Ctrl+Z, to go back to the previous LED
+    Undo,
+    /// Ctrl+C
+    Interrupt,
+}
+
+/// Where the keys pressed while making a keymap come from
+pub(crate) trait KeyCapture {
+    /// Called before waiting for events
+    fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when done waiting, and before printing something in between
+    fn stop(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn next_event(&mut self) -> Result<CaptureEvent>;
+}
+
+/// Reads keys and clicks from the terminal. Keys the terminal or compositor keep to themselves,
+/// like media keys, can't be captured this way
+pub(crate) struct TerminalCapture;
+
+impl KeyCapture for TerminalCapture {
+    fn start(&mut self) -> Result<()> {
+        prepare_terminal_event_capture()
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        default_terminal_settings()
+    }
+
+    async fn next_event(&mut self) -> Result<CaptureEvent> {
+        loop {
+            match crossterm::event::read()? {
+                Event::Key(event) => {
+                    if event.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    if event.modifiers.intersects(KeyModifiers::CONTROL) {
+                        match event.code {
+                            KeyCode::Char('c') => return Ok(CaptureEvent::Interrupt),
+                            KeyCode::Char('z') => return Ok(CaptureEvent::Undo),
+                            _ => {}
+                        }
+                    }
+                    return Ok(CaptureEvent::Key(KeyId::from_key_event(&event)));
+                }
+                Event::Mouse(event) => match event.kind {
+                    MouseEventKind::Down(MouseButton::Left) => return Ok(CaptureEvent::LeftClick),
+                    MouseEventKind::Down(_) => return Ok(CaptureEvent::OtherClick),
+                    _ => continue,
+                },
+                _ => continue,
+            }
+        }
+    }
+}
+
+/// Reads key codes straight from the keyboard in /dev/input, so every key can be captured. The
+/// keyboards are grabbed while this exists, so the keys don't do anything else in the meantime.
+/// Keyboards often send their media and power keys from separate devices, like "Consumer
+/// Control" and "System Control", so those are read and grabbed too
+pub(crate) struct EvdevCapture {
+    events: mpsc::Receiver<InputEvent>,
+    ctrl_held: bool,
+    /// Whether another key was pressed while Ctrl was held, so its release isn't a key of its own
+    ctrl_used: bool,
+    /// Stops the tasks reading the devices, which closes them and lets go of the grab
+    cancellation_token: CancellationToken,
+}
+
+impl EvdevCapture {
+    /// Opens the keyboards at `paths` with the other key devices of the same hardware, or every
+    /// device with keys if there are no paths, and every mouse for the clicks
+    pub(crate) fn open(paths: &[PathBuf]) -> Result<Self> {
+        let mut keyboards = Vec::new();
+        let mut chosen_paths = Vec::new();
+        for path in paths {
+            let device =
+                Device::open(path).with_context(|| format!("Could not open {}", path.display()))?;
+            keyboards.push(device);
+            chosen_paths.push(std::fs::canonicalize(path)?);
+        }
+        let chosen_phys: Vec<String> = keyboards
+            .iter()
+            .filter_map(|device| device.physical_path())
+            .filter_map(phys_prefix)
+            .map(str::to_owned)
+            .collect();
+        let mut mice = Vec::new();
+        for (path, device) in evdev::enumerate() {
+            if chosen_paths.contains(&path) {
+                continue;
+            }
+            if is_mouse(&device) {
+                mice.push(device);
+            } else if has_keys(&device)
+                && (paths.is_empty()
+                    || device
+                        .physical_path()
+                        .and_then(phys_prefix)
+                        .is_some_and(|phys| chosen_phys.iter().any(|chosen| chosen == phys)))
+            {
+                keyboards.push(device);
+            }
+        }
+        if !keyboards.iter().any(is_keyboard) {
+            bail!("No keyboard was found in /dev/input. Reading it needs root or the input group");
+        }
+
+        let (sender, events) = mpsc::channel(100);
+        let cancellation_token = CancellationToken::new();
+        for keyboard in &mut keyboards {
+            let name = keyboard.name().unwrap_or("the keyboard").to_owned();
+            keyboard
+                .grab()
+                .with_context(|| format!("Could not grab {}", name))?;
+        }
+        for device in keyboards.into_iter().chain(mice) {
+            let mut stream = device.into_event_stream()?;
+            let sender = sender.clone();
+            let token = cancellation_token.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::select! {
+                        _ = token.cancelled() => break,
+                        event = stream.next_event() => {
+                            let Ok(event) = event else {
+                                break;
+                            };
+                            if sender.send(event).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            });
+        }
+        Ok(Self::from_events(events, cancellation_token))
+    }
+
+    /// Captures from events that are sent some other way, like in tests
+    pub(crate) fn from_events(
+        events: mpsc::Receiver<InputEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        EvdevCapture {
+            events,
+            ctrl_held: false,
+            ctrl_used: false,
+            cancellation_token,
+        }
+    }
+}
+
+impl Drop for EvdevCapture {
+    fn drop(&mut self) {
+        self.cancellation_token.cancel();
+    }
+}
+
+impl KeyCapture for EvdevCapture {
+    async fn next_event(&mut self) -> Result<CaptureEvent> {
+        loop {
+            let Some(event) = self.events.recv().await else {
+                bail!("The input devices were closed");
+            };
+            let InputEventKind::Key(key) = event.kind() else {
+                continue;
+            };
+            // Ctrl is only a key of its own when it is released without being used for Ctrl+Z or
+            // Ctrl+C. Otherwise its LED would be mapped to Ctrl before the Z arrives
+            if key == Key::KEY_LEFTCTRL || key == Key::KEY_RIGHTCTRL {
+                match event.value() {
+                    0 if self.ctrl_held && !self.ctrl_used => {
+                        self.ctrl_held = false;
+                        return Ok(CaptureEvent::Key(Some(key_id_from_evdev(key))));
+                    }
+                    0 => self.ctrl_held = false,
+                    1 => {
+                        self.ctrl_held = true;
+                        self.ctrl_used = false;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            // 0 is a release and 2 a repeat
+            if event.value() != 1 {
+                continue;
+            }
+            self.ctrl_used = self.ctrl_held;
+            return Ok(match key {
+                Key::BTN_LEFT => CaptureEvent::LeftClick,
+                Key::BTN_RIGHT | Key::BTN_MIDDLE => CaptureEvent::OtherClick,
+                Key::KEY_C if self.ctrl_held => CaptureEvent::Interrupt,
+                Key::KEY_Z if self.ctrl_held => CaptureEvent::Undo,
+                _ => CaptureEvent::Key(Some(key_id_from_evdev(key))),
+            });
+        }
+    }
+}
+
+/// The capture chosen with the evdev and device arguments
+pub(crate) enum AnyCapture {
+    Terminal(TerminalCapture),
+    Evdev(EvdevCapture),
+}
+
+impl AnyCapture {
+    pub(crate) fn from_args(args: &ArgMatches) -> Result<Self> {
+        if !args.get_flag("evdev") {
+            return Ok(AnyCapture::Terminal(TerminalCapture));
+        }
+        let devices: Vec<PathBuf> = args
+            .get_many::<PathBuf>("device")
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+        Ok(AnyCapture::Evdev(EvdevCapture::open(&devices)?))
+    }
+}
+
+impl KeyCapture for AnyCapture {
+    fn start(&mut self) -> Result<()> {
+        match self {
+            AnyCapture::Terminal(capture) => capture.start(),
+            AnyCapture::Evdev(capture) => capture.start(),
+        }
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        match self {
+            AnyCapture::Terminal(capture) => capture.stop(),
+            AnyCapture::Evdev(capture) => capture.stop(),
+        }
+    }
+
+    async fn next_event(&mut self) -> Result<CaptureEvent> {
+        match self {
+            AnyCapture::Terminal(capture) => capture.next_event().await,
+            AnyCapture::Evdev(capture) => capture.next_event().await,
+        }
+    }
+}
+
+fn is_keyboard(device: &Device) -> bool {
+    device
+        .supported_keys()
+        .is_some_and(|keys| keys.contains(Key::KEY_A) && keys.contains(Key::KEY_ENTER))
+}
+
+/// Any device with a key that isn't a button, which includes the media keys of a keyboard that
+/// come from a device of their own
+fn has_keys(device: &Device) -> bool {
+    device
+        .supported_keys()
+        .is_some_and(|keys| keys.iter().any(|key| !is_button(key)))
+}
+
+/// Mouse, joystick and gamepad buttons, in the ranges the kernel gives them
+fn is_button(key: Key) -> bool {
+    matches!(key.code(), 0x100..=0x15f | 0x220..=0x223 | 0x2c0..=0x2e7)
+}
+
+/// The devices of one keyboard share their physical path up to the last part, like
+/// `usb-0000:00:14.0-2/input0` and `usb-0000:00:14.0-2/input1`
+fn phys_prefix(phys: &str) -> Option<&str> {
+    let prefix = phys.rsplit_once('/').map_or(phys, |(prefix, _)| prefix);
+    (!prefix.is_empty()).then_some(prefix)
+}
+
+fn is_mouse(device: &Device) -> bool {
+    device
+        .supported_keys()
+        .is_some_and(|keys| keys.contains(Key::BTN_LEFT))
+}
+
+/// The key of an evdev key code. Codes are named after where the key is on a US layout, like
+/// `KeyId` is
+pub(crate) fn key_id_from_evdev(key: Key) -> KeyId {
+    let code = key.code();
+    // The function keys are in three runs
+    match code {
+        59..=68 => return KeyId::F((code - 58) as u8),
+        87..=88 => return KeyId::F((code - 76) as u8),
+        183..=194 => return KeyId::F((code - 170) as u8),
+        _ => {}
+    }
+    let char = match key {
+        Key::KEY_1 => '1',
+        Key::KEY_2 => '2',
+        Key::KEY_3 => '3',
+        Key::KEY_4 => '4',
+        Key::KEY_5 => '5',
+        Key::KEY_6 => '6',
+        Key::KEY_7 => '7',
+        Key::KEY_8 => '8',
+        Key::KEY_9 => '9',
+        Key::KEY_0 => '0',
+        Key::KEY_Q => 'q',
+        Key::KEY_W => 'w',
+        Key::KEY_E => 'e',
+        Key::KEY_R => 'r',
+        Key::KEY_T => 't',
+        Key::KEY_Y => 'y',
+        Key::KEY_U => 'u',
+        Key::KEY_I => 'i',
+        Key::KEY_O => 'o',
+        Key::KEY_P => 'p',
+        Key::KEY_A => 'a',
+        Key::KEY_S => 's',
+        Key::KEY_D => 'd',
+        Key::KEY_F => 'f',
+        Key::KEY_G => 'g',
+        Key::KEY_H => 'h',
+        Key::KEY_J => 'j',
+        Key::KEY_K => 'k',
+        Key::KEY_L => 'l',
+        Key::KEY_Z => 'z',
+        Key::KEY_X => 'x',
+        Key::KEY_C => 'c',
+        Key::KEY_V => 'v',
+        Key::KEY_B => 'b',
+        Key::KEY_N => 'n',
+        Key::KEY_M => 'm',
+        Key::KEY_GRAVE => '`',
+        Key::KEY_MINUS => '-',
+        Key::KEY_EQUAL => '=',
+        Key::KEY_LEFTBRACE => '[',
+        Key::KEY_RIGHTBRACE => ']',
+        Key::KEY_BACKSLASH => '\\',
+        Key::KEY_SEMICOLON => ';',
+        Key::KEY_APOSTROPHE => '\'',
+        Key::KEY_COMMA => ',',
+        Key::KEY_DOT => '.',
+        Key::KEY_SLASH => '/',
+        Key::KEY_SPACE => ' ',
+        _ => return key_id_from_evdev_name(key),
+    };
+    KeyId::Char(char)
+}
+
+fn key_id_from_evdev_name(key: Key) -> KeyId {
+    match key {
+        Key::KEY_KP0 => KeyId::Keypad('0'),
+        Key::KEY_KP1 => KeyId::Keypad('1'),
+        Key::KEY_KP2 => KeyId::Keypad('2'),
+        Key::KEY_KP3 => KeyId::Keypad('3'),
+        Key::KEY_KP4 => KeyId::Keypad('4'),
+        Key::KEY_KP5 => KeyId::Keypad('5'),
+        Key::KEY_KP6 => KeyId::Keypad('6'),
+        Key::KEY_KP7 => KeyId::Keypad('7'),
+        Key::KEY_KP8 => KeyId::Keypad('8'),
+        Key::KEY_KP9 => KeyId::Keypad('9'),
+        Key::KEY_KPSLASH => KeyId::Keypad('/'),
+        Key::KEY_KPASTERISK => KeyId::Keypad('*'),
+        Key::KEY_KPMINUS => KeyId::Keypad('-'),
+        Key::KEY_KPPLUS => KeyId::Keypad('+'),
+        Key::KEY_KPDOT => KeyId::Keypad('.'),
+        Key::KEY_KPENTER => KeyId::KeypadEnter,
+        Key::KEY_ESC => KeyId::Esc,
+        Key::KEY_TAB => KeyId::Tab,
+        Key::KEY_CAPSLOCK => KeyId::CapsLock,
+        Key::KEY_ENTER => KeyId::Enter,
+        Key::KEY_BACKSPACE => KeyId::Backspace,
+        Key::KEY_INSERT => KeyId::Insert,
+        Key::KEY_DELETE => KeyId::Delete,
+        Key::KEY_HOME => KeyId::Home,
+        Key::KEY_END => KeyId::End,
+        Key::KEY_PAGEUP => KeyId::PageUp,
+        Key::KEY_PAGEDOWN => KeyId::PageDown,
+        Key::KEY_UP => KeyId::Up,
+        Key::KEY_DOWN => KeyId::Down,
+        Key::KEY_LEFT => KeyId::Left,
+        Key::KEY_RIGHT => KeyId::Right,
+        Key::KEY_SYSRQ => KeyId::PrintScreen,
+        Key::KEY_SCROLLLOCK => KeyId::ScrollLock,
+        Key::KEY_PAUSE => KeyId::Pause,
+        Key::KEY_NUMLOCK => KeyId::NumLock,
+        Key::KEY_COMPOSE => KeyId::Menu,
+        Key::KEY_FN => KeyId::Fn,
+        Key::KEY_LEFTSHIFT => KeyId::LeftShift,
+        Key::KEY_RIGHTSHIFT => KeyId::RightShift,
+        Key::KEY_LEFTCTRL => KeyId::LeftCtrl,
+        Key::KEY_RIGHTCTRL => KeyId::RightCtrl,
+        Key::KEY_LEFTALT => KeyId::LeftAlt,
+        Key::KEY_RIGHTALT => KeyId::RightAlt,
+        Key::KEY_LEFTMETA => KeyId::LeftSuper,
+        Key::KEY_RIGHTMETA => KeyId::RightSuper,
+        Key::KEY_102ND => KeyId::IsoBackslash,
+        Key::KEY_PLAYPAUSE => KeyId::MediaPlayPause,
+        Key::KEY_STOPCD => KeyId::MediaStop,
+        Key::KEY_NEXTSONG => KeyId::MediaNext,
+        Key::KEY_PREVIOUSSONG => KeyId::MediaPrevious,
+        Key::KEY_MUTE => KeyId::Mute,
+        Key::KEY_VOLUMEUP => KeyId::VolumeUp,
+        Key::KEY_VOLUMEDOWN => KeyId::VolumeDown,
+        _ => KeyId::Evdev(key.code()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use evdev::{EventType, InputEvent, Key};
+    use tokio::sync::mpsc;
+    use tokio_util::sync::CancellationToken;
+
+    use super::{
+        is_button, key_id_from_evdev, phys_prefix, CaptureEvent, EvdevCapture, KeyCapture,
+    };
+    use crate::core::key_id::KeyId;
+
+    fn key_event(key: Key, value: i32) -> InputEvent {
+        InputEvent::new(EventType::KEY, key.code(), value)
+    }
+
+    #[test]
+    fn test_key_id_from_evdev() {
+        assert_eq!(key_id_from_evdev(Key::KEY_A), KeyId::Char('a'));
+        assert_eq!(key_id_from_evdev(Key::KEY_F1), KeyId::F(1));
+        assert_eq!(key_id_from_evdev(Key::KEY_F12), KeyId::F(12));
+        assert_eq!(key_id_from_evdev(Key::KEY_F24), KeyId::F(24));
+        assert_eq!(key_id_from_evdev(Key::KEY_102ND), KeyId::IsoBackslash);
+        assert_eq!(key_id_from_evdev(Key::KEY_KP1), KeyId::Keypad('1'));
+        assert_eq!(key_id_from_evdev(Key::new(0x290)), KeyId::Evdev(0x290));
+    }
+
+    #[test]
+    fn test_phys_prefix() {
+        assert_eq!(
+            phys_prefix("usb-0000:00:14.0-2/input1"),
+            Some("usb-0000:00:14.0-2")
+        );
+        assert_eq!(phys_prefix("isa0060/serio0/input0"), Some("isa0060/serio0"));
+        assert_eq!(phys_prefix("LNXPWRBN/button"), Some("LNXPWRBN"));
+        assert_eq!(phys_prefix(""), None);
+        assert!(is_button(Key::BTN_LEFT));
+        assert!(!is_button(Key::KEY_PLAYPAUSE));
+    }
+
+    #[tokio::test]
+    async fn test_evdev_capture() {
+        let (sender, receiver) = mpsc::channel(100);
+        let mut capture = EvdevCapture::from_events(receiver, CancellationToken::new());
+        for event in [
+            InputEvent::new(EventType::SYNCHRONIZATION, 0, 0),
+            key_event(Key::KEY_PLAYPAUSE, 1),
+            key_event(Key::KEY_PLAYPAUSE, 2),
+            key_event(Key::KEY_PLAYPAUSE, 0),
+            key_event(Key::BTN_LEFT, 1),
+            key_event(Key::BTN_RIGHT, 1),
+            key_event(Key::KEY_C, 1),
+            key_event(Key::KEY_RIGHTCTRL, 1),
+            key_event(Key::KEY_RIGHTCTRL, 0),
+            key_event(Key::KEY_RIGHTCTRL, 1),
+            key_event(Key::KEY_Z, 1),
+            key_event(Key::KEY_C, 1),
+            key_event(Key::KEY_RIGHTCTRL, 0),
+        ] {
+            sender.send(event).await.unwrap();
+        }
+        let mut events = Vec::new();
+        for _ in 0..7 {
+            events.push(capture.next_event().await.unwrap());
+        }
+        assert_eq!(
+            events,
+            vec![
+                CaptureEvent::Key(Some(KeyId::MediaPlayPause)),
+                CaptureEvent::LeftClick,
+                CaptureEvent::OtherClick,
+                CaptureEvent::Key(Some(KeyId::Char('c'))),
+                CaptureEvent::Key(Some(KeyId::RightCtrl)),
+                CaptureEvent::Undo,
+                CaptureEvent::Interrupt,
+            ]
+        );
+        drop(sender);
+        assert!(capture.next_event().await.is_err());
+    }
+}
@@ -0,0 +1,374 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::bail;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventState, MediaKeyCode, ModifierKeyCode};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A physical key on the keyboard. In the keymap and config it is written as:
+///
+/// - Letters and digits as themselves, like `A` and `1`
+/// - The other keys of the main block by name: `Grave`, `Minus`, `Equal`, `LeftBracket`,
+///   `RightBracket`, `Backslash`, `Semicolon`, `Quote`, `Comma`, `Period`, `Slash` and `Space`.
+///   Keys that don't exist on a US layout are written as `Char_` and what they type, like `Char_ö`
+/// - Function keys as `F1` to `F24`
+/// - Number pad keys as `KP_0` to `KP_9`, `KP_Divide`, `KP_Multiply`, `KP_Minus`, `KP_Plus`,
+///   `KP_Period` and `KP_Enter`
+/// - `ISO_Backslash` for the extra key next to left shift on ISO keyboards
+/// - Keys that only have an evdev key code as `Evdev_` and the code, like `Evdev_464`
+/// - Every other key by the name of its variant below, like `LeftShift` or `PageUp`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) enum KeyId {
+    /// A key of the main block, by what it types on a US layout without shift. Letters are lower
+    /// case
+    Char(char),
+    F(u8),
+    /// A key of the number pad, by what it types
+    Keypad(char),
+    KeypadEnter,
+    Esc,
+    Tab,
+    CapsLock,
+    Enter,
+    Backspace,
+    Insert,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Up,
+    Down,
+    Left,
+    Right,
+    PrintScreen,
+    ScrollLock,
+    Pause,
+    NumLock,
+    Menu,
+    Fn,
+    LeftShift,
+    RightShift,
+    LeftCtrl,
+    RightCtrl,
+    LeftAlt,
+    RightAlt,
+    LeftSuper,
+    RightSuper,
+    IsoBackslash,
+    MediaPlayPause,
+    MediaStop,
+    MediaNext,
+    MediaPrevious,
+    Mute,
+    VolumeUp,
+    VolumeDown,
+    /// A key without a name here, by its evdev key code
+    Evdev(u16),
+}
+
+/// Keys that are written by name and have no data
+const NAMED_KEYS: [(KeyId, &str); 38] = [
+    (KeyId::KeypadEnter, "KP_Enter"),
+    (KeyId::Esc, "Esc"),
+    (KeyId::Tab, "Tab"),
+    (KeyId::CapsLock, "CapsLock"),
+    (KeyId::Enter, "Enter"),
+    (KeyId::Backspace, "Backspace"),
+    (KeyId::Insert, "Insert"),
+    (KeyId::Delete, "Delete"),
+    (KeyId::Home, "Home"),
+    (KeyId::End, "End"),
+    (KeyId::PageUp, "PageUp"),
+    (KeyId::PageDown, "PageDown"),
+    (KeyId::Up, "Up"),
+    (KeyId::Down, "Down"),
+    (KeyId::Left, "Left"),
+    (KeyId::Right, "Right"),
+    (KeyId::PrintScreen, "PrintScreen"),
+    (KeyId::ScrollLock, "ScrollLock"),
+    (KeyId::Pause, "Pause"),
+    (KeyId::NumLock, "NumLock"),
+    (KeyId::Menu, "Menu"),
+    (KeyId::Fn, "Fn"),
+    (KeyId::LeftShift, "LeftShift"),
+    (KeyId::RightShift, "RightShift"),
+    (KeyId::LeftCtrl, "LeftCtrl"),
+    (KeyId::RightCtrl, "RightCtrl"),
+    (KeyId::LeftAlt, "LeftAlt"),
+    (KeyId::RightAlt, "RightAlt"),
+    (KeyId::LeftSuper, "LeftSuper"),
+    (KeyId::RightSuper, "RightSuper"),
+    (KeyId::IsoBackslash, "ISO_Backslash"),
+    (KeyId::MediaPlayPause, "MediaPlayPause"),
+    (KeyId::MediaStop, "MediaStop"),
+    (KeyId::MediaNext, "MediaNext"),
+    (KeyId::MediaPrevious, "MediaPrevious"),
+    (KeyId::Mute, "Mute"),
+    (KeyId::VolumeUp, "VolumeUp"),
+    (KeyId::VolumeDown, "VolumeDown"),
+];
+
+/// Keys of the main block that are written by name
+const CHAR_NAMES: [(char, &str); 12] = [
+    ('`', "Grave"),
+    ('-', "Minus"),
+    ('=', "Equal"),
+    ('[', "LeftBracket"),
+    (']', "RightBracket"),
+    ('\\', "Backslash"),
+    (';', "Semicolon"),
+    ('\'', "Quote"),
+    (',', "Comma"),
+    ('.', "Period"),
+    ('/', "Slash"),
+    (' ', "Space"),
+];
+
+const KEYPAD_NAMES: [(char, &str); 5] = [
+    ('/', "Divide"),
+    ('*', "Multiply"),
+    ('-', "Minus"),
+    ('+', "Plus"),
+    ('.', "Period"),
+];
+
+impl fmt::Display for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyId::Char(char) if char.is_ascii_alphanumeric() => {
+                write!(f, "{}", char.to_ascii_uppercase())
+            }
+            KeyId::Char(char) => match CHAR_NAMES.iter().find(|(named, _)| named == char) {
+                Some((_, name)) => write!(f, "{}", name),
+                None => write!(f, "Char_{}", char),
+            },
+            KeyId::F(number) => write!(f, "F{}", number),
+            KeyId::Keypad(char) => match KEYPAD_NAMES.iter().find(|(named, _)| named == char) {
+                Some((_, name)) => write!(f, "KP_{}", name),
+                None => write!(f, "KP_{}", char),
+            },
+            KeyId::Evdev(code) => write!(f, "Evdev_{}", code),
+            _ => {
+                let (_, name) = NAMED_KEYS
+                    .iter()
+                    .find(|(key, _)| key == self)
+                    .expect("Every key without data has a name");
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
+impl FromStr for KeyId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        if let Some((key, _)) = NAMED_KEYS.iter().find(|(_, name)| *name == s) {
+            return Ok(*key);
+        }
+        if let Some((char, _)) = CHAR_NAMES.iter().find(|(_, name)| *name == s) {
+            return Ok(KeyId::Char(*char));
+        }
+        let mut chars = s.chars();
+        if let (Some(char), None) = (chars.next(), chars.next()) {
+            if char.is_ascii_alphanumeric() {
+                return Ok(KeyId::Char(char.to_ascii_lowercase()));
+            }
+        }
+        if let Some(char) = s.strip_prefix("Char_") {
+            let mut chars = char.chars();
+            if let (Some(char), None) = (chars.next(), chars.next()) {
+                return Ok(KeyId::Char(char));
+            }
+        }
+        if let Some(key) = s
+            .strip_prefix('F')
+            .and_then(|n| n.parse::<u8>().ok())
+            .and_then(KeyId::function_key)
+        {
+            return Ok(key);
+        }
+        if let Some(code) = s.strip_prefix("Evdev_").and_then(|n| n.parse::<u16>().ok()) {
+            return Ok(KeyId::Evdev(code));
+        }
+        if let Some(key) = s.strip_prefix("KP_") {
+            if let Some((char, _)) = KEYPAD_NAMES.iter().find(|(_, name)| *name == key) {
+                return Ok(KeyId::Keypad(*char));
+            }
+            let mut chars = key.chars();
+            if let (Some(char), None) = (chars.next(), chars.next()) {
+                return Ok(KeyId::Keypad(char));
+            }
+        }
+        bail!("Unknown key: {}", s)
+    }
+}
+
+impl Serialize for KeyId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Keymaps and configs used to store crossterm's `KeyCode`, like `!Char q` or `!F 1`. Those are
+/// still read, and written in the new format the next time the file is saved
+impl<'de> Deserialize<'de> for KeyId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = serde_yaml::Value::deserialize(deserializer)?;
+        // Digits are read as numbers if they aren't quoted
+        let text = match &value {
+            serde_yaml::Value::String(text) => Some(text.clone()),
+            serde_yaml::Value::Number(number) => Some(number.to_string()),
+            _ => None,
+        };
+        if let Some(Ok(key)) = text.as_deref().map(KeyId::from_str) {
+            return Ok(key);
+        }
+        let key_code = serde_yaml::from_value::<KeyCode>(value.clone())
+            .map_err(|_| serde::de::Error::custom(format!("Unknown key: {:?}", value)))?;
+        KeyId::from_key_code(key_code).ok_or_else(|| {
+            serde::de::Error::custom(format!("{:?} is not a key on the keyboard", key_code))
+        })
+    }
+}
+
+impl KeyId {
+    /// `F1` to `F24`, the function keys a keyboard can have
+    pub(crate) fn function_key(number: u8) -> Option<Self> {
+        (1..=24).contains(&number).then_some(KeyId::F(number))
+    }
+
+    /// The key a terminal reports. None for codes that aren't a single key
+    pub(crate) fn from_key_code(key_code: KeyCode) -> Option<Self> {
+        Some(match key_code {
+            KeyCode::Char(char) => KeyId::Char(char.to_ascii_lowercase()),
+            KeyCode::F(number) => return KeyId::function_key(number),
+            KeyCode::Esc => KeyId::Esc,
+            KeyCode::Tab | KeyCode::BackTab => KeyId::Tab,
+            KeyCode::CapsLock => KeyId::CapsLock,
+            KeyCode::Enter => KeyId::Enter,
+            KeyCode::Backspace => KeyId::Backspace,
+            KeyCode::Insert => KeyId::Insert,
+            KeyCode::Delete => KeyId::Delete,
+            KeyCode::Home => KeyId::Home,
+            KeyCode::End => KeyId::End,
+            KeyCode::PageUp => KeyId::PageUp,
+            KeyCode::PageDown => KeyId::PageDown,
+            KeyCode::Up => KeyId::Up,
+            KeyCode::Down => KeyId::Down,
+            KeyCode::Left => KeyId::Left,
+            KeyCode::Right => KeyId::Right,
+            KeyCode::PrintScreen => KeyId::PrintScreen,
+            KeyCode::ScrollLock => KeyId::ScrollLock,
+            KeyCode::Pause => KeyId::Pause,
+            KeyCode::NumLock => KeyId::NumLock,
+            KeyCode::Menu => KeyId::Menu,
+            KeyCode::KeypadBegin => KeyId::Keypad('5'),
+            KeyCode::Modifier(modifier) => match modifier {
+                ModifierKeyCode::LeftShift => KeyId::LeftShift,
+                ModifierKeyCode::RightShift => KeyId::RightShift,
+                ModifierKeyCode::LeftControl => KeyId::LeftCtrl,
+                ModifierKeyCode::RightControl => KeyId::RightCtrl,
+                ModifierKeyCode::LeftAlt => KeyId::LeftAlt,
+                ModifierKeyCode::RightAlt | ModifierKeyCode::IsoLevel3Shift => KeyId::RightAlt,
+                ModifierKeyCode::LeftSuper | ModifierKeyCode::LeftMeta => KeyId::LeftSuper,
+                ModifierKeyCode::RightSuper | ModifierKeyCode::RightMeta => KeyId::RightSuper,
+                _ => return None,
+            },
+            KeyCode::Media(media) => match media {
+                MediaKeyCode::PlayPause | MediaKeyCode::Play | MediaKeyCode::Pause => {
+                    KeyId::MediaPlayPause
+                }
+                MediaKeyCode::Stop => KeyId::MediaStop,
+                MediaKeyCode::TrackNext => KeyId::MediaNext,
+                MediaKeyCode::TrackPrevious => KeyId::MediaPrevious,
+                MediaKeyCode::MuteVolume => KeyId::Mute,
+                MediaKeyCode::RaiseVolume => KeyId::VolumeUp,
+                MediaKeyCode::LowerVolume => KeyId::VolumeDown,
+                _ => return None,
+            },
+            KeyCode::Null => return None,
+        })
+    }
+
+    /// Like `from_key_code`, but tells the number pad apart, on terminals that report it
+    pub(crate) fn from_key_event(event: &KeyEvent) -> Option<Self> {
+        if event.state.contains(KeyEventState::KEYPAD) {
+            match event.code {
+                KeyCode::Char(char) => return Some(KeyId::Keypad(char)),
+                KeyCode::Enter => return Some(KeyId::KeypadEnter),
+                _ => {}
+            }
+        }
+        Self::from_key_code(event.code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use super::{KeyId, CHAR_NAMES, NAMED_KEYS};
+
+    #[test]
+    fn test_round_trip() {
+        let mut keys: Vec<KeyId> = NAMED_KEYS.iter().map(|(key, _)| *key).collect();
+        keys.extend(CHAR_NAMES.iter().map(|(char, _)| KeyId::Char(*char)));
+        keys.extend([
+            KeyId::Char('a'),
+            KeyId::Char('7'),
+            KeyId::Char('ö'),
+            KeyId::F(13),
+            KeyId::Keypad('0'),
+            KeyId::Keypad('+'),
+            KeyId::Evdev(464),
+        ]);
+        for key in keys {
+            assert_eq!(KeyId::from_str(&key.to_string()).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(KeyId::Char('q').to_string(), "Q");
+        assert_eq!(KeyId::Char('\\').to_string(), "Backslash");
+        assert_eq!(KeyId::IsoBackslash.to_string(), "ISO_Backslash");
+        assert_eq!(KeyId::Keypad('*').to_string(), "KP_Multiply");
+        assert_eq!(KeyId::Keypad('1').to_string(), "KP_1");
+        assert!(KeyId::from_str("q").is_ok());
+        assert!(KeyId::from_str("Nope").is_err());
+        assert!(KeyId::from_str("KP_10").is_err());
+    }
+
+    #[test]
+    fn test_function_keys() {
+        assert_eq!(KeyId::from_str("F1").unwrap(), KeyId::F(1));
+        assert_eq!(KeyId::from_str("F24").unwrap(), KeyId::F(24));
+        for name in ["F0", "F25", "F200", "F256"] {
+            assert!(KeyId::from_str(name).is_err(), "{} was accepted", name);
+        }
+    }
+
+    #[test]
+    fn test_old_keymap() {
+        let key_led_map: HashMap<KeyId, u32> = serde_yaml::from_str(
+            "!Char 'q': 1\n!F 5: 2\nEsc: 3\n!Modifier LeftShift: 4\nBackTab: 5\n7: 6\n",
+        )
+        .unwrap();
+        assert_eq!(
+            key_led_map,
+            HashMap::from([
+                (KeyId::Char('q'), 1),
+                (KeyId::F(5), 2),
+                (KeyId::Esc, 3),
+                (KeyId::LeftShift, 4),
+                (KeyId::Tab, 5),
+                (KeyId::Char('7'), 6),
+            ])
+        );
+        let written = serde_yaml::to_string(&HashMap::from([(KeyId::Char('q'), 1)])).unwrap();
+        assert_eq!(written, "Q: 1\n");
+    }
+}
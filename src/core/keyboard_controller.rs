@@ -11,8 +11,12 @@ use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 
+use crate::core::auto_keymap::ZoneLayout;
 use crate::core::utils::compute_light_curve_for_color;
 
+/// What OpenRGB puts in the cells of a zone matrix that have no LED
+const NO_LED_IN_MATRIX: u32 = u32::MAX;
+
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct KeyboardControllerMessage {
     led: Option<u32>,
@@ -37,6 +41,16 @@ impl KeyboardControllerMessage {
     }
 }
 
+#[cfg(test)]
+impl KeyboardControllerMessage {
+    pub(crate) fn led(&self) -> Option<u32> {
+        self.led
+    }
+    pub(crate) fn color(&self) -> Color {
+        self.color
+    }
+}
+
 pub(crate) struct KeyboardController {
     client: OpenRGB<TcpStream>,
     controller_id: u32,
@@ -105,6 +119,36 @@ impl KeyboardController {
         self.controller.leds.len() as u32
     }
 
+    /// The names OpenRGB gives the LEDs, like "Key: Left Shift"
+    pub(crate) fn led_names(&self) -> Vec<String> {
+        self.controller
+            .leds
+            .iter()
+            .map(|led| led.name.clone())
+            .collect()
+    }
+
+    pub(crate) fn zone_layouts(&self) -> Vec<ZoneLayout> {
+        self.controller
+            .zones
+            .iter()
+            .map(|zone| ZoneLayout {
+                leds_count: zone.leds_count,
+                matrix: zone.matrix.as_ref().map(|matrix| {
+                    matrix
+                        .as_rows()
+                        .into_iter()
+                        .map(|row| {
+                            row.into_iter()
+                                .map(|led| (led != NO_LED_IN_MATRIX).then_some(led))
+                                .collect()
+                        })
+                        .collect()
+                }),
+            })
+            .collect()
+    }
+
     pub(crate) fn run(
         keyboard_controller: Arc<Mutex<KeyboardController>>,
         task_tracker: &TaskTracker,
@@ -0,0 +1,191 @@
+use std::collections::{BTreeSet, HashMap};
+
+use rgb::RGB8;
+
+use super::key_id::KeyId;
+use super::keymap::Keymap;
+
+/// Characters per key of width 1.0, including the gap to the next key
+const KEY_WIDTH: f32 = 5.;
+/// Color of the keys that are in no module
+const UNUSED_COLOR: RGB8 = RGB8::new(60, 60, 60);
+
+/// Draws the keyboard as text with 24-bit ANSI colors. Every LED of `colors` gets its color, and
+/// the rest of the keys are gray. LEDs without a known position are left out. Without `ansi`,
+/// only the labels are drawn, for output that isn't a terminal
+pub(crate) fn render_diagram(keymap: &Keymap, colors: &HashMap<u32, RGB8>, ansi: bool) -> String {
+    let mut labels: HashMap<u32, String> = HashMap::new();
+    for (key, led) in &keymap.key_led_map {
+        labels.insert(*led, short_label(*key));
+    }
+    let leds: BTreeSet<u32> = labels
+        .keys()
+        .chain(keymap.geometry.keys())
+        .chain(colors.keys())
+        .copied()
+        .collect();
+
+    // Rows are two lines apart, so half rows like those of KLE layouts fit in between
+    let mut lines: Vec<Vec<(char, Option<RGB8>)>> = Vec::new();
+    for led in leds {
+        let Some(position) = keymap.led_position(led) else {
+            continue;
+        };
+        let line = (position.y * 2.).round() as usize;
+        // Negative positions become 0. Every key is at least one character wide, even if it is
+        // too narrow to leave a gap to the next one
+        let start = (position.x * KEY_WIDTH).round() as usize;
+        let end = (((position.x + position.width) * KEY_WIDTH).round() as usize)
+            .saturating_sub(1)
+            .max(start + 1);
+        if lines.len() <= line {
+            lines.resize(line + 1, Vec::new());
+        }
+        let line = &mut lines[line];
+        if line.len() < end {
+            line.resize(end, (' ', None));
+        }
+        let color = colors.get(&led).copied().unwrap_or(UNUSED_COLOR);
+        let label: Vec<char> = labels.get(&led).map_or(Vec::new(), |l| l.chars().collect());
+        let label_width = label.len().min(end - start);
+        let label_start = start + (end - start - label_width) / 2;
+        for (column, cell) in line[start..end].iter_mut().enumerate() {
+            let char = (start + column)
+                .checked_sub(label_start)
+                .and_then(|index| label.get(index).filter(|_| index < label_width))
+                .copied()
+                .unwrap_or(' ');
+            *cell = (char, Some(color));
+        }
+    }
+
+    let mut out = String::new();
+    for line in lines {
+        let mut current = None;
+        for (char, color) in line {
+            if ansi && color != current {
+                match color {
+                    Some(color) => out.push_str(&color_escape(color)),
+                    None => out.push_str("\x1b[0m"),
+                }
+                current = color;
+            }
+            out.push(char);
+        }
+        if current.is_some() {
+            out.push_str("\x1b[0m");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Sets the background to `color`, and the text to black or white, whichever is easier to read
+pub(crate) fn color_escape(color: RGB8) -> String {
+    let luma = 0.299 * color.r as f32 + 0.587 * color.g as f32 + 0.114 * color.b as f32;
+    let text = if luma > 128. { "30" } else { "97" };
+    format!(
+        "\x1b[48;2;{};{};{}m\x1b[{}m",
+        color.r, color.g, color.b, text
+    )
+}
+
+/// A name of the key that fits on a key of width 1.0
+fn short_label(key: KeyId) -> String {
+    let label = match key {
+        KeyId::Esc => "Esc",
+        KeyId::CapsLock => "Caps",
+        KeyId::Enter => "Ent",
+        KeyId::KeypadEnter => "Ent",
+        KeyId::Backspace => "Bksp",
+        KeyId::Insert => "Ins",
+        KeyId::Delete => "Del",
+        KeyId::PageUp => "PgUp",
+        KeyId::PageDown => "PgDn",
+        KeyId::Up => "↑",
+        KeyId::Down => "↓",
+        KeyId::Left => "←",
+        KeyId::Right => "→",
+        KeyId::PrintScreen => "PrSc",
+        KeyId::ScrollLock => "ScLk",
+        KeyId::NumLock => "NmLk",
+        KeyId::LeftShift | KeyId::RightShift => "Shft",
+        KeyId::LeftCtrl | KeyId::RightCtrl => "Ctrl",
+        KeyId::LeftAlt => "Alt",
+        KeyId::RightAlt => "AltG",
+        KeyId::LeftSuper | KeyId::RightSuper => "Supr",
+        KeyId::IsoBackslash => "\\",
+        KeyId::MediaPlayPause => "Play",
+        KeyId::MediaStop => "Stop",
+        KeyId::MediaNext => "Next",
+        KeyId::MediaPrevious => "Prev",
+        KeyId::VolumeUp => "Vol+",
+        KeyId::VolumeDown => "Vol-",
+        KeyId::Char(' ') => "",
+        KeyId::Char(char) | KeyId::Keypad(char) => return char.to_uppercase().to_string(),
+        _ => return key.to_string(),
+    };
+    label.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rgb::RGB8;
+
+    use super::{color_escape, render_diagram};
+    use crate::core::key_id::KeyId;
+    use crate::core::keymap::{Keymap, LedPosition};
+
+    #[test]
+    fn test_render_diagram() {
+        let mut keymap = Keymap {
+            first_in_row: vec![0, 2],
+            ..Default::default()
+        };
+        keymap.key_led_map.insert(KeyId::Esc, 0);
+        keymap.key_led_map.insert(KeyId::Char('q'), 1);
+        keymap.key_led_map.insert(KeyId::LeftShift, 2);
+        keymap.geometry.insert(
+            2,
+            LedPosition {
+                x: 0.,
+                y: 1.5,
+                width: 2.,
+            },
+        );
+        let red = RGB8::new(255, 0, 0);
+        let diagram = render_diagram(&keymap, &HashMap::from([(1, red)]), true);
+        let plain = regex::Regex::new("\x1b\\[[0-9;]*m")
+            .unwrap()
+            .replace_all(&diagram, "");
+        assert_eq!(plain, "Esc   Q  \n\n\n  Shft   \n");
+        assert!(diagram.contains(&format!("{} Q  ", color_escape(red))));
+        assert_eq!(
+            render_diagram(&keymap, &HashMap::from([(1, red)]), false),
+            plain
+        );
+    }
+
+    #[test]
+    fn test_render_narrow_keys() {
+        let mut keymap = Keymap::default();
+        for (led, x, width) in [(0, 0., 0.), (1, 1., 0.1), (2, -1., 1.)] {
+            keymap.geometry.insert(led, LedPosition { x, y: 0., width });
+        }
+        assert_eq!(render_diagram(&keymap, &HashMap::new(), false), "      \n");
+    }
+
+    #[test]
+    fn test_color_escape() {
+        assert_eq!(
+            color_escape(RGB8::new(255, 255, 0)),
+            "\x1b[48;2;255;255;0m\x1b[30m"
+        );
+        assert_eq!(
+            color_escape(RGB8::new(0, 0, 80)),
+            "\x1b[48;2;0;0;80m\x1b[97m"
+        );
+    }
+}
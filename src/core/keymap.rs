@@ -1,11 +1,167 @@
 use std::collections::{BTreeSet, HashMap};
 
-use crossterm::event::KeyCode;
 use serde::{Deserialize, Serialize};
 
+use super::key_id::KeyId;
+
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct Keymap {
-    pub(crate) key_led_map: HashMap<KeyCode, u32>,
+    pub(crate) key_led_map: HashMap<KeyId, u32>,
     pub(crate) first_in_row: Vec<u32>,
     pub(crate) skip_indicies: BTreeSet<u32>,
+    /// Where each LED is on the keyboard. LEDs that aren't in here are placed with
+    /// `first_in_row` instead
+    #[serde(default)]
+    pub(crate) geometry: HashMap<u32, LedPosition>,
+}
+
+/// The top left corner and width of an LED's key, in keys. A letter key is 1.0 wide
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub(crate) struct LedPosition {
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    #[serde(default = "default_width")]
+    pub(crate) width: f32,
+}
+
+fn default_width() -> f32 {
+    1.
+}
+
+impl LedPosition {
+    pub(crate) fn center(&self) -> (f32, f32) {
+        (self.x + self.width / 2., self.y + 0.5)
+    }
+}
+
+impl Keymap {
+    /// The position of an LED from `geometry`, or if it's not there, a guess that treats LEDs
+    /// as one key wide and going from left to right from the first LED of each row. None if
+    /// neither knows about the LED
+    pub(crate) fn led_position(&self, led: u32) -> Option<LedPosition> {
+        if let Some(position) = self.geometry.get(&led) {
+            return Some(*position);
+        }
+        // Rows are in the order they were pressed, top to bottom, but their LEDs don't have to
+        // be numbered in that order
+        let (row, &first) = self
+            .first_in_row
+            .iter()
+            .enumerate()
+            .filter(|(_, &first)| first <= led)
+            .max_by_key(|(_, &first)| first)?;
+        if self.skip_indicies.contains(&led) {
+            return None;
+        }
+        let x = (first..led)
+            .filter(|index| !self.skip_indicies.contains(index))
+            .count();
+        Some(LedPosition {
+            x: x as f32,
+            y: row as f32,
+            width: default_width(),
+        })
+    }
+
+    /// Maps `key` to `led`. Any other key of the LED is unmapped
+    pub(crate) fn map_key(&mut self, key: KeyId, led: u32) {
+        self.forget_led(led);
+        self.key_led_map.insert(key, led);
+    }
+
+    /// Returns the LED the key was mapped to
+    pub(crate) fn unmap_key(&mut self, key: KeyId) -> Option<u32> {
+        self.key_led_map.remove(&key)
+    }
+
+    /// Marks the LED as one without a key
+    pub(crate) fn unmap_led(&mut self, led: u32) {
+        self.forget_led(led);
+        self.skip_indicies.insert(led);
+    }
+
+    /// Removes everything known about the LED's key, as if it hadn't been pressed yet
+    pub(crate) fn forget_led(&mut self, led: u32) {
+        self.key_led_map.retain(|_, key_led| *key_led != led);
+        self.skip_indicies.remove(&led);
+    }
+
+    /// The positions of a module's LEDs, in the same order
+    pub(crate) fn module_positions(&self, module_leds: &[Option<u32>]) -> Vec<Option<LedPosition>> {
+        module_leds
+            .iter()
+            .map(|led| led.and_then(|led| self.led_position(led)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Keymap, LedPosition};
+    use crate::core::key_id::KeyId;
+
+    fn position(x: f32, y: f32) -> LedPosition {
+        LedPosition { x, y, width: 1. }
+    }
+
+    #[test]
+    fn test_led_position_from_rows() {
+        let keymap = Keymap {
+            // The second row is numbered before the first
+            first_in_row: vec![10, 0],
+            skip_indicies: [2].into(),
+            ..Default::default()
+        };
+        assert_eq!(keymap.led_position(0), Some(position(0., 1.)));
+        assert_eq!(keymap.led_position(3), Some(position(2., 1.)));
+        assert_eq!(keymap.led_position(2), None);
+        assert_eq!(keymap.led_position(12), Some(position(2., 0.)));
+    }
+
+    #[test]
+    fn test_led_position_from_geometry() {
+        let mut keymap = Keymap {
+            first_in_row: vec![0],
+            ..Default::default()
+        };
+        let space = LedPosition {
+            x: 3.75,
+            y: 4.,
+            width: 6.25,
+        };
+        keymap.geometry.insert(5, space);
+        assert_eq!(keymap.led_position(5), Some(space));
+        assert_eq!(keymap.led_position(4), Some(position(4., 0.)));
+        assert_eq!(
+            keymap.module_positions(&[Some(5), None, Some(1)]),
+            vec![Some(space), None, Some(position(1., 0.))]
+        );
+        assert_eq!(space.center(), (6.875, 4.5));
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut keymap = Keymap::default();
+        keymap.map_key(KeyId::Char('q'), 1);
+        keymap.unmap_led(2);
+        keymap.map_key(KeyId::Char('w'), 2);
+        assert_eq!(keymap.key_led_map[&KeyId::Char('w')], 2);
+        assert!(keymap.skip_indicies.is_empty());
+        // Another key for the same LED replaces the first one
+        keymap.map_key(KeyId::Char('e'), 1);
+        assert!(!keymap.key_led_map.contains_key(&KeyId::Char('q')));
+        keymap.unmap_led(1);
+        assert!(!keymap.key_led_map.contains_key(&KeyId::Char('e')));
+        assert_eq!(keymap.skip_indicies, [1].into());
+        assert_eq!(keymap.unmap_key(KeyId::Char('w')), Some(2));
+        assert_eq!(keymap.unmap_key(KeyId::Char('w')), None);
+        keymap.forget_led(1);
+        assert!(keymap.skip_indicies.is_empty());
+    }
+
+    #[test]
+    fn test_no_geometry() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.led_position(0), None);
+    }
 }
@@ -0,0 +1,301 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Context};
+use serde_json::Value;
+
+use super::key_id::KeyId;
+use super::keymap::{Keymap, LedPosition};
+
+/// A key of a KLE layout, in keys from the top left corner
+#[derive(Debug, PartialEq)]
+pub(crate) struct KleKey {
+    /// Every legend of the key, like "!" and "1"
+    pub(crate) legends: Vec<String>,
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) width: f32,
+}
+
+/// Parses the JSON downloaded from keyboard-layout-editor.com (KLE), which QMK and VIA use too.
+/// Rotation is ignored, so rotated keys end up where they would be without it
+pub(crate) fn parse_kle(json: &str) -> anyhow::Result<Vec<KleKey>> {
+    let layout: Value = serde_json::from_str(json)
+        .context("Invalid JSON. Use the JSON download of KLE, not the raw data")?;
+    let Some(rows) = layout.as_array() else {
+        bail!("A KLE layout is a list of rows");
+    };
+    let mut keys = Vec::new();
+    let mut y = 0.;
+    // Rows are lists of keys. The layout can start with an object of metadata
+    for row in rows.iter().filter_map(|row| row.as_array()) {
+        let mut x = 0.;
+        let mut width = 1.;
+        for item in row {
+            match item {
+                // Changes the position and size of the next key
+                Value::Object(properties) => {
+                    let property = |name| properties.get(name).and_then(|value| value.as_f64());
+                    x += property("x").unwrap_or(0.) as f32;
+                    y += property("y").unwrap_or(0.) as f32;
+                    if let Some(w) = property("w") {
+                        width = w as f32;
+                    }
+                }
+                Value::String(legends) => {
+                    keys.push(KleKey {
+                        legends: legends
+                            .split('\n')
+                            .map(decode_entities)
+                            .filter(|legend| !legend.is_empty())
+                            .collect(),
+                        x,
+                        y,
+                        width,
+                    });
+                    x += width;
+                    width = 1.;
+                }
+                _ => bail!("Unexpected item in a KLE row: {}", item),
+            }
+        }
+        y += 1.;
+    }
+    Ok(keys)
+}
+
+fn decode_entities(legend: &str) -> String {
+    legend
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+/// The key a legend stands for. `seen` counts how many keys with the same legend came before,
+/// because the second Shift, Ctrl or Alt of a layout is the right one, and the second 1 or Enter
+/// is on the number pad
+pub(crate) fn key_id_from_legend(legend: &str, seen: usize) -> Option<KeyId> {
+    let side = |left, right| Some(if seen == 0 { left } else { right });
+    let mut chars = legend.chars();
+    if let (Some(char), None) = (chars.next(), chars.next()) {
+        return match char {
+            '↑' => Some(KeyId::Up),
+            '↓' => Some(KeyId::Down),
+            '←' => Some(KeyId::Left),
+            '→' => Some(KeyId::Right),
+            '0'..='9' | '/' | '*' | '-' | '+' | '.' if seen > 0 => Some(KeyId::Keypad(char)),
+            _ => Some(KeyId::Char(char.to_ascii_lowercase())),
+        };
+    }
+    let lowercase = legend.to_lowercase();
+    if let Some(number) = lowercase.strip_prefix('f') {
+        if let Some(key) = number.parse::<u8>().ok().and_then(KeyId::function_key) {
+            return Some(key);
+        }
+    }
+    match lowercase.as_str() {
+        "esc" | "escape" => Some(KeyId::Esc),
+        "tab" => Some(KeyId::Tab),
+        "caps lock" | "caps" | "capslock" => Some(KeyId::CapsLock),
+        "enter" | "return" => side(KeyId::Enter, KeyId::KeypadEnter),
+        "backspace" | "back space" | "bksp" => Some(KeyId::Backspace),
+        "space" | "spacebar" => Some(KeyId::Char(' ')),
+        "up" => Some(KeyId::Up),
+        "down" => Some(KeyId::Down),
+        "left" => Some(KeyId::Left),
+        "right" => Some(KeyId::Right),
+        "home" => Some(KeyId::Home),
+        "end" => Some(KeyId::End),
+        "pgup" | "page up" | "pageup" => Some(KeyId::PageUp),
+        "pgdn" | "page down" | "pagedown" => Some(KeyId::PageDown),
+        "ins" | "insert" => Some(KeyId::Insert),
+        "del" | "delete" => Some(KeyId::Delete),
+        "prtsc" | "print screen" | "prtscn" => Some(KeyId::PrintScreen),
+        "scroll lock" | "scrlk" => Some(KeyId::ScrollLock),
+        "pause" | "pause break" => Some(KeyId::Pause),
+        "num lock" | "numlock" => Some(KeyId::NumLock),
+        "menu" | "app" | "apps" => Some(KeyId::Menu),
+        "fn" => Some(KeyId::Fn),
+        "shift" => side(KeyId::LeftShift, KeyId::RightShift),
+        "ctrl" | "control" => side(KeyId::LeftCtrl, KeyId::RightCtrl),
+        "alt" => side(KeyId::LeftAlt, KeyId::RightAlt),
+        "altgr" | "alt gr" => Some(KeyId::RightAlt),
+        "win" | "super" | "meta" | "gui" | "cmd" => side(KeyId::LeftSuper, KeyId::RightSuper),
+        _ => None,
+    }
+}
+
+/// Gives every LED of a key in the layout its position. Returns the legends of the keys that
+/// couldn't be matched to a key in `key_led_map`. Every LED is placed once, so a key whose
+/// legends only match LEDs that are placed already is unmatched too
+pub(crate) fn import_layout(keymap: &mut Keymap, keys: &[KleKey]) -> Vec<String> {
+    // The key of every legend. `seen` depends on the keys before, so these are worked out in
+    // the order of the layout
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut legend_keys: Vec<Vec<Option<KeyId>>> = Vec::new();
+    for key in keys {
+        legend_keys.push(
+            key.legends
+                .iter()
+                .map(|legend| {
+                    key_id_from_legend(legend, *seen.get(&legend.to_lowercase()).unwrap_or(&0))
+                })
+                .collect(),
+        );
+        for legend in &key.legends {
+            *seen.entry(legend.to_lowercase()).or_default() += 1;
+        }
+    }
+
+    // Keys are matched on their first legend before any other. Otherwise the Home of the 7 on
+    // the number pad could take the LED of the Home key, if the number pad isn't in the keymap
+    let mut key_leds: Vec<Option<u32>> = vec![None; keys.len()];
+    let mut placed: HashSet<u32> = HashSet::new();
+    for first_legend_only in [true, false] {
+        for (key_led, codes) in key_leds.iter_mut().zip(&legend_keys) {
+            if key_led.is_some() {
+                continue;
+            }
+            let legends = if first_legend_only { 1 } else { codes.len() };
+            *key_led = codes
+                .iter()
+                .take(legends)
+                .flatten()
+                .filter_map(|code| keymap.key_led_map.get(code).copied())
+                .find(|led| !placed.contains(led));
+            placed.extend(*key_led);
+        }
+    }
+
+    let mut unmatched = Vec::new();
+    for (key, led) in keys.iter().zip(key_leds) {
+        match led {
+            Some(led) => {
+                keymap.geometry.insert(
+                    led,
+                    LedPosition {
+                        x: key.x,
+                        y: key.y,
+                        width: key.width,
+                    },
+                );
+            }
+            None => unmatched.push(key.legends.join(" ")),
+        }
+    }
+    unmatched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{import_layout, parse_kle, KleKey};
+    use crate::core::key_id::KeyId;
+    use crate::core::keymap::{Keymap, LedPosition};
+
+    const LAYOUT: &str = r#"[
+        {"name": "Test"},
+        ["Esc", {"x": 1}, "F1"],
+        [{"y": 0.5}, "!\n1", "Q", {"w": 2}, "Backspace"],
+        [{"w": 2.25}, "Shift", "&lt;", {"w": 2.75}, "Shift"]
+    ]"#;
+
+    /// The ANSI 104 preset of KLE, with its number pad
+    const FULL_SIZE_LAYOUT: &str = r##"[
+        ["Esc", {"x": 1}, "F1", "F2", "F3", "F4", {"x": 0.5}, "F5", "F6", "F7", "F8", {"x": 0.5}, "F9", "F10", "F11", "F12", {"x": 0.25}, "PrtSc", "Scroll Lock", "Pause\nBreak"],
+        [{"y": 0.5}, "~\n`", "!\n1", "@\n2", "#\n3", "$\n4", "%\n5", "^\n6", "&\n7", "*\n8", "(\n9", ")\n0", "_\n-", "+\n=", {"w": 2}, "Backspace", {"x": 0.25}, "Insert", "Home", "PgUp", {"x": 0.25}, "Num Lock", "/", "*", "-"],
+        [{"w": 1.5}, "Tab", "Q", "W", "E", "R", "T", "Y", "U", "I", "O", "P", "{\n[", "}\n]", {"w": 1.5}, "|\n\\", {"x": 0.25}, "Delete", "End", "PgDn", {"x": 0.25}, "7\nHome", "8\n↑", "9\nPgUp", {"h": 2}, "+"],
+        [{"w": 1.75}, "Caps Lock", "A", "S", "D", "F", "G", "H", "J", "K", "L", ":\n;", "\"\n'", {"w": 2.25}, "Enter", {"x": 3.5}, "4\n←", "5", "6\n→"],
+        [{"w": 2.25}, "Shift", "Z", "X", "C", "V", "B", "N", "M", "&lt;\n,", "&gt;\n.", "?\n/", {"w": 2.75}, "Shift", {"x": 1.25}, "↑", {"x": 1.25}, "1\nEnd", "2\n↓", "3\nPgDn", {"h": 2}, "Enter"],
+        [{"w": 1.25}, "Ctrl", {"w": 1.25}, "Win", {"w": 1.25}, "Alt", {"a": 7, "w": 6.25}, "", {"a": 4, "w": 1.25}, "Alt", {"w": 1.25}, "Win", {"w": 1.25}, "Menu", {"w": 1.25}, "Ctrl", {"x": 0.25}, "←", "↓", "→", {"x": 0.25, "w": 2}, "0\nIns", ".\nDel"]
+    ]"##;
+
+    #[test]
+    fn test_parse_kle() {
+        let keys = parse_kle(LAYOUT).unwrap();
+        assert_eq!(keys.len(), 8);
+        assert_eq!(
+            keys[1],
+            KleKey {
+                legends: vec!["F1".to_owned()],
+                x: 2.,
+                y: 0.,
+                width: 1.,
+            }
+        );
+        assert_eq!(
+            keys[4],
+            KleKey {
+                legends: vec!["Backspace".to_owned()],
+                x: 2.,
+                y: 1.5,
+                width: 2.,
+            }
+        );
+        assert_eq!(keys[6].legends, vec!["<".to_owned()]);
+        assert_eq!((keys[7].x, keys[7].y, keys[7].width), (3.25, 2.5, 2.75));
+        assert!(parse_kle("[[1]]").is_err());
+    }
+
+    #[test]
+    fn test_import_layout() {
+        let mut keymap = Keymap::default();
+        keymap.key_led_map.insert(KeyId::Esc, 0);
+        keymap.key_led_map.insert(KeyId::Char('1'), 1);
+        keymap.key_led_map.insert(KeyId::Char('q'), 2);
+        keymap.key_led_map.insert(KeyId::LeftShift, 3);
+        keymap.key_led_map.insert(KeyId::RightShift, 4);
+        let unmatched = import_layout(&mut keymap, &parse_kle(LAYOUT).unwrap());
+        assert_eq!(unmatched, vec!["F1", "Backspace", "<"]);
+        assert_eq!(
+            keymap.geometry[&1],
+            LedPosition {
+                x: 0.,
+                y: 1.5,
+                width: 1.,
+            }
+        );
+        assert_eq!(keymap.geometry[&2].x, 1.);
+        assert_eq!(keymap.geometry[&3].width, 2.25);
+        assert_eq!(keymap.geometry[&4].x, 3.25);
+    }
+
+    #[test]
+    fn test_import_full_size_layout() {
+        let keys = parse_kle(FULL_SIZE_LAYOUT).unwrap();
+        assert_eq!(keys.len(), 104);
+        let mut keymap = Keymap::default();
+        keymap.key_led_map.insert(KeyId::Insert, 0);
+        keymap.key_led_map.insert(KeyId::Delete, 1);
+        keymap.key_led_map.insert(KeyId::Home, 2);
+        keymap.key_led_map.insert(KeyId::Up, 3);
+        keymap.key_led_map.insert(KeyId::Char('0'), 4);
+        keymap.key_led_map.insert(KeyId::Char('.'), 5);
+        keymap.key_led_map.insert(KeyId::Keypad('0'), 6);
+        keymap.key_led_map.insert(KeyId::Enter, 7);
+        keymap.key_led_map.insert(KeyId::KeypadEnter, 8);
+        // The number pad's 7 and 8 aren't in the keymap, so their Home and ↑ would match the
+        // LEDs of the real keys
+        let unmatched = import_layout(&mut keymap, &keys);
+        assert!(unmatched.contains(&"7 Home".to_owned()));
+        assert!(unmatched.contains(&"8 ↑".to_owned()));
+        // The number pad's . isn't in the keymap either, and its Del mustn't move Delete
+        assert!(unmatched.contains(&". Del".to_owned()));
+        assert!(!unmatched.contains(&"0 Ins".to_owned()));
+        assert_eq!((keymap.geometry[&0].x, keymap.geometry[&0].y), (15.25, 1.5));
+        assert_eq!((keymap.geometry[&1].x, keymap.geometry[&1].y), (15.25, 2.5));
+        assert_eq!((keymap.geometry[&2].x, keymap.geometry[&2].y), (16.25, 1.5));
+        assert_eq!((keymap.geometry[&3].x, keymap.geometry[&3].y), (16.25, 4.5));
+        assert_eq!((keymap.geometry[&4].x, keymap.geometry[&4].y), (10., 1.5));
+        assert_eq!((keymap.geometry[&5].x, keymap.geometry[&5].y), (10.25, 4.5));
+        assert_eq!(
+            keymap.geometry[&6],
+            LedPosition {
+                x: 18.5,
+                y: 5.5,
+                width: 2.,
+            }
+        );
+        assert_eq!(keymap.geometry[&7].x, 12.75);
+        assert_eq!(keymap.geometry[&8].x, 21.5);
+    }
+}
@@ -1,7 +1,18 @@
+pub mod audio;
+pub mod auto_keymap;
 pub mod config_creator;
 pub mod config_manager;
 pub mod constants;
+pub mod key_capture;
+pub mod key_id;
 pub mod keyboard_controller;
+pub mod keyboard_diagram;
 pub mod keymap;
+pub mod kle;
 pub mod module;
+pub mod progress_bar;
+#[cfg(test)]
+pub mod scripted;
+pub mod sway_config;
+pub mod sway_ipc;
 pub mod utils;
@@ -1,21 +1,33 @@
 use std::time::Duration;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use tokio::sync::mpsc::Sender;
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 
+use crate::core::progress_bar::ProgressBarStyle;
 use crate::core::utils;
 use crate::core::utils::rgb_to_hex;
-use crate::modules::media_playing::MediaModule;
-use crate::modules::noise::{NoiseModule, NoiseModuleOptions};
+use crate::modules::binding_mode::{BindingModeModule, BindingModeModuleOptions};
+use crate::modules::keyboard_layout::{KeyboardLayoutModule, KeyboardLayoutModuleOptions};
+use crate::modules::media_playing::{
+    MediaColorRule, MediaField, MediaModule, MediaModuleOptions, PlayerSelection,
+};
+use crate::modules::mic_mute::{MicMuteModule, MicMuteModuleOptions};
+use crate::modules::noise::{NoiseFunction, NoiseModule, NoiseModuleOptions};
+use crate::modules::shortcut_hints::{ShortcutHintsModule, ShortcutHintsModuleOptions};
 use crate::modules::starfield::{StarfieldModule, StarfieldModuleOptions};
-use crate::modules::workspaces::WorkspacesModule;
+use crate::modules::volume::{VolumeModule, VolumeModuleOptions};
+use crate::modules::workspaces::{
+    UrgentAnimation, WindowCountDisplay, WorkspacesModule, WorkspacesModuleOptions,
+};
 
 use super::keyboard_controller::KeyboardControllerMessage;
+use super::keymap::Keymap;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct Module {
+    #[serde(deserialize_with = "deserialize_module_type")]
     pub(crate) module_type: ModuleType,
     pub(crate) module_leds: Vec<Option<u32>>,
 }
@@ -29,12 +41,33 @@ impl Module {
     }
 }
 
-#[derive(Serialize, Debug, Copy, Clone, Deserialize)]
+/// Modules that didn't have any options used to be unit variants, which are written as plain
+/// strings in the config. Those are read as the module with default options
+fn deserialize_module_type<'de, D>(deserializer: D) -> Result<ModuleType, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = serde_yaml::Value::deserialize(deserializer)?;
+    match value.as_str() {
+        Some("Workspaces") => Ok(ModuleType::Workspaces(Default::default())),
+        Some("Media") => Ok(ModuleType::Media(Default::default())),
+        _ => serde_yaml::from_value(value).map_err(serde::de::Error::custom),
+    }
+}
+
+pub(crate) type SettingHandler = Box<fn(&mut ModuleType)>;
+
+#[derive(Serialize, Debug, Clone, Deserialize)]
 pub(crate) enum ModuleType {
-    Workspaces,
-    Media,
+    Workspaces(WorkspacesModuleOptions),
+    Media(MediaModuleOptions),
     Starfield(StarfieldModuleOptions),
     Noise(NoiseModuleOptions),
+    BindingMode(BindingModeModuleOptions),
+    ShortcutHints(ShortcutHintsModuleOptions),
+    KeyboardLayout(KeyboardLayoutModuleOptions),
+    Volume(VolumeModuleOptions),
+    MicMute(MicMuteModuleOptions),
 }
 
 impl ModuleType {
@@ -44,42 +77,103 @@ impl ModuleType {
         cancellation_token: CancellationToken,
         sender: Sender<KeyboardControllerMessage>,
         module_leds: Vec<Option<u32>>,
+        keymap: &Keymap,
     ) {
         match self {
-            ModuleType::Workspaces => {
-                WorkspacesModule::run(task_tracker, cancellation_token, sender, module_leds)
-            }
-            ModuleType::Media => {
-                MediaModule::run(task_tracker, cancellation_token, sender, module_leds)
-            }
+            ModuleType::Workspaces(opts) => WorkspacesModule::run(
+                task_tracker,
+                cancellation_token,
+                sender,
+                module_leds,
+                opts.clone(),
+            ),
+            ModuleType::Media(opts) => MediaModule::run(
+                task_tracker,
+                cancellation_token,
+                sender,
+                module_leds,
+                opts.clone(),
+                keymap.key_led_map.clone(),
+            ),
             ModuleType::Starfield(opts) => {
                 StarfieldModule::run(task_tracker, cancellation_token, sender, module_leds, *opts)
             }
             ModuleType::Noise(opts) => {
-                NoiseModule::run(task_tracker, cancellation_token, sender, module_leds, *opts)
+                let positions = keymap.module_positions(&module_leds);
+                NoiseModule::run(
+                    task_tracker,
+                    cancellation_token,
+                    sender,
+                    module_leds,
+                    opts.clone(),
+                    positions,
+                )
+            }
+            ModuleType::BindingMode(opts) => BindingModeModule::run(
+                task_tracker,
+                cancellation_token,
+                sender,
+                module_leds,
+                opts.clone(),
+                keymap.key_led_map.clone(),
+            ),
+            ModuleType::ShortcutHints(opts) => ShortcutHintsModule::run(
+                task_tracker,
+                cancellation_token,
+                sender,
+                module_leds,
+                opts.clone(),
+                keymap.key_led_map.clone(),
+            ),
+            ModuleType::KeyboardLayout(opts) => KeyboardLayoutModule::run(
+                task_tracker,
+                cancellation_token,
+                sender,
+                module_leds,
+                opts.clone(),
+            ),
+            ModuleType::Volume(opts) => {
+                VolumeModule::run(task_tracker, cancellation_token, sender, module_leds, *opts)
+            }
+            ModuleType::MicMute(opts) => {
+                MicMuteModule::run(task_tracker, cancellation_token, sender, module_leds, *opts)
             }
         }
     }
 
     pub(crate) fn name(&self) -> &'static str {
         match self {
-            ModuleType::Workspaces => "Sway Workspaces",
-            ModuleType::Media => "Media Player Monitor",
+            ModuleType::Workspaces(_) => "Workspaces",
+            ModuleType::Media(_) => "Media Player Monitor",
             ModuleType::Starfield(_) => "Starfield Ambient",
             ModuleType::Noise(_) => "Noise",
+            ModuleType::BindingMode(_) => "Sway Binding Mode",
+            ModuleType::ShortcutHints(_) => "Sway Shortcut Hints",
+            ModuleType::KeyboardLayout(_) => "Keyboard Layout",
+            ModuleType::Volume(_) => "Volume",
+            ModuleType::MicMute(_) => "Microphone Mute",
         }
     }
     pub(crate) fn desc(&self) -> &'static str {
         match self {
-            ModuleType::Workspaces => "",
-            ModuleType::Media => "Shows media playhead and platform on keyboard",
+            ModuleType::Workspaces(_) => "Shows the state of each Sway, i3 or Hyprland workspace on its key",
+            ModuleType::Media(_) => "Shows media playhead and platform on keyboard",
             ModuleType::Starfield(_) => "",
             ModuleType::Noise(_) => "Noise thing",
+            ModuleType::BindingMode(_) => "Lights up keys while a Sway binding mode is active",
+            ModuleType::ShortcutHints(_) => {
+                "Lights up the keys with a binding while a modifier is held. Needs a Sway bar with mode hide"
+            }
+            ModuleType::KeyboardLayout(_) => "Shows the active keyboard layout in Sway as a color",
+            ModuleType::Volume(_) => {
+                "Shows the volume of the default output as a bar for a moment when it changes"
+            }
+            ModuleType::MicMute(_) => "Lights up while the default microphone is muted",
         }
     }
-    pub(crate) fn add_all_settings(&self) -> (Vec<String>, Vec<Box<fn(&mut ModuleType)>>) {
+    pub(crate) fn add_all_settings(&self) -> (Vec<String>, Vec<SettingHandler>) {
         let mut choices_names: Vec<String> = Vec::new();
-        let mut choices_handlers: Vec<Box<fn(&mut ModuleType)>> = Vec::new();
+        let mut choices_handlers: Vec<SettingHandler> = Vec::new();
         macro_rules! add_choice {
             ($val: expr, $name: expr, $handler: expr) => {
                 choices_names.push(format!("{} [Current: {:?}]", $name, $val));
@@ -87,8 +181,226 @@ impl ModuleType {
             };
         }
         match self {
-            ModuleType::Workspaces => todo!(),
-            ModuleType::Media => todo!(),
+            ModuleType::Workspaces(opts) => {
+                let output_tints = opts
+                    .output_tints
+                    .iter()
+                    .map(|(output, tint)| format!("{}: {}", output, rgb_to_hex(*tint)))
+                    .collect::<Vec<String>>();
+                add_choice!(output_tints, "Set tint of output", |opts| {
+                    if let ModuleType::Workspaces(ref mut opts) = opts {
+                        println!("Name of output (as shown by swaymsg -t get_outputs): ");
+                        let output = utils::get_input("Invalid output name", |input| {
+                            (!input.is_empty()).then(|| input.to_owned())
+                        })
+                        .unwrap();
+                        opts.output_tints
+                            .insert(output, utils::get_color_input().unwrap());
+                    }
+                });
+                add_choice!(output_tints, "Remove tint of output", |opts| {
+                    if let ModuleType::Workspaces(ref mut opts) = opts {
+                        println!("Name of output: ");
+                        let output = utils::get_input("Invalid output name", |input| {
+                            (!input.is_empty()).then(|| input.to_owned())
+                        })
+                        .unwrap();
+                        opts.output_tints.remove(&output);
+                    }
+                });
+                add_choice!(opts.window_count_display, "Window count display", |opts| {
+                    if let ModuleType::Workspaces(ref mut opts) = opts {
+                        let all = WindowCountDisplay::all();
+                        let names = all.map(|display| format!("{:?}", display));
+                        opts.window_count_display = all[utils::choose_option(&names).unwrap()];
+                    }
+                });
+                add_choice!(
+                    opts.max_window_count,
+                    "Window count for full brightness",
+                    |opts| {
+                        if let ModuleType::Workspaces(ref mut opts) = opts {
+                            opts.max_window_count = utils::get_input("Invalid number", |input| {
+                                input.parse::<u32>().ok().filter(|&count| count > 0)
+                            })
+                            .unwrap();
+                        }
+                    }
+                );
+                add_choice!(opts.urgent_animation, "Urgent animation", |opts| {
+                    if let ModuleType::Workspaces(ref mut opts) = opts {
+                        let all = UrgentAnimation::all();
+                        let names = all.map(|animation| format!("{:?}", animation));
+                        opts.urgent_animation = all[utils::choose_option(&names).unwrap()];
+                    }
+                });
+                add_choice!(rgb_to_hex(opts.urgent_color), "Urgent color", |opts| {
+                    if let ModuleType::Workspaces(ref mut opts) = opts {
+                        opts.urgent_color = utils::get_color_input().unwrap()
+                    }
+                });
+                add_choice!(
+                    opts.urgent_animation_period,
+                    "Urgent animation period (in seconds)",
+                    |opts| {
+                        if let ModuleType::Workspaces(ref mut opts) = opts {
+                            opts.urgent_animation_period = Duration::from_secs_f64(
+                                utils::get_input("Invalid number", |input| {
+                                    input.parse::<f64>().ok().filter(|&secs| secs > 0.)
+                                })
+                                .unwrap(),
+                            )
+                        }
+                    }
+                );
+            }
+            ModuleType::Media(opts) => {
+                add_choice!(opts.player_selection, "Player selection", |opts| {
+                    if let ModuleType::Media(ref mut opts) = opts {
+                        opts.player_selection =
+                            match utils::choose_option(&PlayerSelection::names()).unwrap() {
+                                0 => PlayerSelection::FirstRuleMatch,
+                                1 => PlayerSelection::MostRecent,
+                                2 => {
+                                    println!("Bus name of the player (e.g. spotify): ");
+                                    PlayerSelection::Pinned(
+                                        utils::get_input("Invalid bus name", |input| {
+                                            (!input.is_empty()).then(|| input.to_owned())
+                                        })
+                                        .unwrap(),
+                                    )
+                                }
+                                _ => PlayerSelection::Split,
+                            };
+                    }
+                });
+                let color_rules = opts
+                    .color_rules
+                    .iter()
+                    .map(|rule| {
+                        format!(
+                            "{:?} matches {}: {}",
+                            rule.field,
+                            rule.pattern,
+                            rgb_to_hex(rule.color)
+                        )
+                    })
+                    .collect::<Vec<String>>();
+                add_choice!(color_rules, "Add color rule", |opts| {
+                    if let ModuleType::Media(ref mut opts) = opts {
+                        println!("Field to match: ");
+                        let all = MediaField::all();
+                        let names = all.map(|field| format!("{:?}", field));
+                        let field = all[utils::choose_option(&names).unwrap()];
+                        println!("Regex to match the field against: ");
+                        let pattern = utils::get_input("Invalid regex", |input| {
+                            regex::Regex::new(input).ok().map(|_| input.to_owned())
+                        })
+                        .unwrap();
+                        opts.color_rules.push(MediaColorRule {
+                            field,
+                            pattern,
+                            color: utils::get_color_input().unwrap(),
+                        });
+                    }
+                });
+                add_choice!(color_rules, "Remove color rule", |opts| {
+                    if let ModuleType::Media(ref mut opts) = opts {
+                        if opts.color_rules.is_empty() {
+                            println!("There are no color rules");
+                            return;
+                        }
+                        let names = opts
+                            .color_rules
+                            .iter()
+                            .map(|rule| format!("{:?} matches {}", rule.field, rule.pattern))
+                            .collect::<Vec<String>>();
+                        let index = utils::choose_option(&names).unwrap();
+                        opts.color_rules.remove(index);
+                    }
+                });
+                add_choice!(rgb_to_hex(opts.paused_color), "Paused color", |opts| {
+                    if let ModuleType::Media(ref mut opts) = opts {
+                        opts.paused_color = utils::get_color_input().unwrap()
+                    }
+                });
+                add_choice!(
+                    rgb_to_hex(opts.default_color),
+                    "Color when no rule matches",
+                    |opts| {
+                        if let ModuleType::Media(ref mut opts) = opts {
+                            opts.default_color = utils::get_color_input().unwrap()
+                        }
+                    }
+                );
+                macro_rules! add_status_key_choice {
+                    ($field: ident, $name: expr) => {
+                        add_choice!(opts.status_keys.$field, $name, |opts| {
+                            if let ModuleType::Media(ref mut opts) = opts {
+                                println!("Press the key, or Escape for none");
+                                opts.status_keys.$field = utils::read_key().unwrap();
+                            }
+                        });
+                    };
+                }
+                add_status_key_choice!(shuffle, "Shuffle status key");
+                add_status_key_choice!(loop_status, "Loop status key");
+                add_status_key_choice!(rate, "Playback rate status key");
+                add_status_key_choice!(next, "Next track status key");
+                add_status_key_choice!(previous, "Previous track status key");
+                add_choice!(rgb_to_hex(opts.status_color), "Status key color", |opts| {
+                    if let ModuleType::Media(ref mut opts) = opts {
+                        opts.status_color = utils::get_color_input().unwrap()
+                    }
+                });
+                add_choice!(
+                    rgb_to_hex(opts.loop_track_color),
+                    "Status key color when looping a track",
+                    |opts| {
+                        if let ModuleType::Media(ref mut opts) = opts {
+                            opts.loop_track_color = utils::get_color_input().unwrap()
+                        }
+                    }
+                );
+                add_choice!(opts.album_art_color, "Use album art color", |opts| {
+                    if let ModuleType::Media(ref mut opts) = opts {
+                        opts.album_art_color = utils::confirm_action(
+                            "Color the progress bar after the album art? [y/N] ",
+                            false,
+                        )
+                        .unwrap();
+                    }
+                });
+                add_choice!(opts.progress_bar.style, "Progress bar style", |opts| {
+                    if let ModuleType::Media(ref mut opts) = opts {
+                        let all = ProgressBarStyle::all();
+                        let names = all.map(|style| format!("{:?}", style));
+                        opts.progress_bar.style = all[utils::choose_option(&names).unwrap()];
+                    }
+                });
+                add_choice!(opts.progress_bar.reverse, "Reverse progress bar", |opts| {
+                    if let ModuleType::Media(ref mut opts) = opts {
+                        opts.progress_bar.reverse = utils::confirm_action(
+                            "Fill the progress bar from the last LED? [y/N] ",
+                            false,
+                        )
+                        .unwrap();
+                    }
+                });
+                add_choice!(
+                    opts.progress_bar.segment_length,
+                    "Progress bar segment length",
+                    |opts| {
+                        if let ModuleType::Media(ref mut opts) = opts {
+                            opts.progress_bar.segment_length =
+                                utils::get_input("Invalid number", |input| {
+                                    input.parse::<u32>().ok().filter(|&length| length > 0)
+                                })
+                                .unwrap();
+                        }
+                    }
+                );
+            }
             ModuleType::Starfield(opts) => {
                 add_choice!(rgb_to_hex(opts.background), "Background", |opts| {
                     if let ModuleType::Starfield(ref mut opts) = opts {
@@ -117,14 +429,57 @@ impl ModuleType {
                 });
             }
             ModuleType::Noise(opts) => {
-                add_choice!(rgb_to_hex(opts.color1), "First color", |opts| {
+                add_choice!(opts.function, "Noise function", |opts| {
+                    if let ModuleType::Noise(ref mut opts) = opts {
+                        let all = NoiseFunction::all();
+                        let names = all.map(|function| format!("{:?}", function));
+                        opts.function = all[utils::choose_option(&names).unwrap()];
+                    }
+                });
+                add_choice!(opts.octaves, "Octaves (for Fbm)", |opts| {
+                    if let ModuleType::Noise(ref mut opts) = opts {
+                        opts.octaves = utils::get_input("Invalid number", |input| {
+                            input.parse::<usize>().ok().filter(|&octaves| octaves > 0)
+                        })
+                        .unwrap();
+                    }
+                });
+                add_choice!(opts.seed, "Seed", |opts| {
+                    if let ModuleType::Noise(ref mut opts) = opts {
+                        println!("Seed (leave empty for a random one on every start): ");
+                        opts.seed = utils::get_input("Invalid number", |input| {
+                            if input.is_empty() {
+                                Some(None)
+                            } else {
+                                input.parse::<u32>().ok().map(Some)
+                            }
+                        })
+                        .unwrap();
+                    }
+                });
+                let gradient = opts
+                    .gradient
+                    .iter()
+                    .map(|color| rgb_to_hex(*color))
+                    .collect::<Vec<String>>();
+                add_choice!(gradient, "Add gradient color", |opts| {
                     if let ModuleType::Noise(ref mut opts) = opts {
-                        opts.color1 = utils::get_color_input().unwrap()
+                        opts.gradient.push(utils::get_color_input().unwrap());
                     }
                 });
-                add_choice!(rgb_to_hex(opts.color2), "Second color", |opts| {
+                add_choice!(gradient, "Remove gradient color", |opts| {
                     if let ModuleType::Noise(ref mut opts) = opts {
-                        opts.color2 = utils::get_color_input().unwrap()
+                        if opts.gradient.is_empty() {
+                            println!("There are no gradient colors");
+                            return;
+                        }
+                        let names = opts
+                            .gradient
+                            .iter()
+                            .map(|color| rgb_to_hex(*color))
+                            .collect::<Vec<String>>();
+                        let index = utils::choose_option(&names).unwrap();
+                        opts.gradient.remove(index);
                     }
                 });
                 add_choice!(opts.speed, "Speed", |opts| {
@@ -142,15 +497,223 @@ impl ModuleType {
                     }
                 });
             }
+            ModuleType::BindingMode(opts) => {
+                let mode_colors = opts
+                    .mode_colors
+                    .iter()
+                    .map(|(mode, color)| format!("{}: {}", mode, rgb_to_hex(*color)))
+                    .collect::<Vec<String>>();
+                add_choice!(mode_colors, "Set color of mode", |opts| {
+                    if let ModuleType::BindingMode(ref mut opts) = opts {
+                        println!("Name of binding mode: ");
+                        let mode = utils::get_input("Invalid mode name", |input| {
+                            (!input.is_empty()).then(|| input.to_owned())
+                        })
+                        .unwrap();
+                        opts.mode_colors
+                            .insert(mode, utils::get_color_input().unwrap());
+                    }
+                });
+                add_choice!(mode_colors, "Remove color of mode", |opts| {
+                    if let ModuleType::BindingMode(ref mut opts) = opts {
+                        println!("Name of binding mode: ");
+                        let mode = utils::get_input("Invalid mode name", |input| {
+                            (!input.is_empty()).then(|| input.to_owned())
+                        })
+                        .unwrap();
+                        opts.mode_colors.remove(&mode);
+                    }
+                });
+                add_choice!(
+                    rgb_to_hex(opts.default_color),
+                    "Color of other modes",
+                    |opts| {
+                        if let ModuleType::BindingMode(ref mut opts) = opts {
+                            opts.default_color = utils::get_color_input().unwrap()
+                        }
+                    }
+                );
+                add_choice!(opts.only_bound_keys, "Only light bound keys", |opts| {
+                    if let ModuleType::BindingMode(ref mut opts) = opts {
+                        opts.only_bound_keys = utils::confirm_action(
+                            "Only light the keys that have a binding in the mode? [y/N] ",
+                            false,
+                        )
+                        .unwrap();
+                    }
+                });
+            }
+            ModuleType::ShortcutHints(opts) => {
+                add_choice!(opts.modifier, "Modifier", |opts| {
+                    if let ModuleType::ShortcutHints(ref mut opts) = opts {
+                        println!("Modifier as written in the Sway config (e.g. Mod4): ");
+                        opts.modifier = utils::get_input("Invalid modifier", |input| {
+                            (!input.is_empty()).then(|| input.to_owned())
+                        })
+                        .unwrap();
+                    }
+                });
+                add_choice!(
+                    rgb_to_hex(opts.workspace_color),
+                    "Workspace binding color",
+                    |opts| {
+                        if let ModuleType::ShortcutHints(ref mut opts) = opts {
+                            opts.workspace_color = utils::get_color_input().unwrap()
+                        }
+                    }
+                );
+                add_choice!(rgb_to_hex(opts.exec_color), "Exec binding color", |opts| {
+                    if let ModuleType::ShortcutHints(ref mut opts) = opts {
+                        opts.exec_color = utils::get_color_input().unwrap()
+                    }
+                });
+                add_choice!(
+                    rgb_to_hex(opts.focus_color),
+                    "Focus binding color",
+                    |opts| {
+                        if let ModuleType::ShortcutHints(ref mut opts) = opts {
+                            opts.focus_color = utils::get_color_input().unwrap()
+                        }
+                    }
+                );
+                add_choice!(rgb_to_hex(opts.move_color), "Move binding color", |opts| {
+                    if let ModuleType::ShortcutHints(ref mut opts) = opts {
+                        opts.move_color = utils::get_color_input().unwrap()
+                    }
+                });
+                add_choice!(
+                    rgb_to_hex(opts.other_color),
+                    "Other binding color",
+                    |opts| {
+                        if let ModuleType::ShortcutHints(ref mut opts) = opts {
+                            opts.other_color = utils::get_color_input().unwrap()
+                        }
+                    }
+                );
+            }
+            ModuleType::KeyboardLayout(opts) => {
+                let layout_colors = opts
+                    .layout_colors
+                    .iter()
+                    .map(|(layout, color)| format!("{}: {}", layout, rgb_to_hex(*color)))
+                    .collect::<Vec<String>>();
+                add_choice!(layout_colors, "Set color of layout", |opts| {
+                    if let ModuleType::KeyboardLayout(ref mut opts) = opts {
+                        println!("Name of layout (as shown by swaymsg -t get_inputs) or its code in the Sway config, like us: ");
+                        let layout = utils::get_input("Invalid layout name", |input| {
+                            (!input.is_empty()).then(|| input.to_owned())
+                        })
+                        .unwrap();
+                        opts.layout_colors
+                            .insert(layout, utils::get_color_input().unwrap());
+                    }
+                });
+                add_choice!(layout_colors, "Remove color of layout", |opts| {
+                    if let ModuleType::KeyboardLayout(ref mut opts) = opts {
+                        println!("Name of layout: ");
+                        let layout = utils::get_input("Invalid layout name", |input| {
+                            (!input.is_empty()).then(|| input.to_owned())
+                        })
+                        .unwrap();
+                        opts.layout_colors.remove(&layout);
+                    }
+                });
+                add_choice!(
+                    rgb_to_hex(opts.default_color),
+                    "Color of other layouts",
+                    |opts| {
+                        if let ModuleType::KeyboardLayout(ref mut opts) = opts {
+                            opts.default_color = utils::get_color_input().unwrap()
+                        }
+                    }
+                );
+                add_choice!(opts.identifier, "Keyboard to follow", |opts| {
+                    if let ModuleType::KeyboardLayout(ref mut opts) = opts {
+                        println!("Identifier of the keyboard (as shown by swaymsg -t get_inputs), or nothing for every keyboard: ");
+                        opts.identifier = utils::get_input("Invalid identifier", |input| {
+                            Some((!input.is_empty()).then(|| input.to_owned()))
+                        })
+                        .unwrap();
+                    }
+                });
+            }
+            ModuleType::Volume(opts) => {
+                add_choice!(rgb_to_hex(opts.volume_color), "Volume color", |opts| {
+                    if let ModuleType::Volume(ref mut opts) = opts {
+                        opts.volume_color = utils::get_color_input().unwrap()
+                    }
+                });
+                add_choice!(rgb_to_hex(opts.muted_color), "Muted color", |opts| {
+                    if let ModuleType::Volume(ref mut opts) = opts {
+                        opts.muted_color = utils::get_color_input().unwrap()
+                    }
+                });
+                add_choice!(
+                    opts.show_duration,
+                    "Time shown after a change (in seconds)",
+                    |opts| {
+                        if let ModuleType::Volume(ref mut opts) = opts {
+                            opts.show_duration = Duration::from_secs_f64(
+                                utils::get_input("Invalid number", |input| {
+                                    input.parse::<f64>().ok().filter(|&secs| secs >= 0.)
+                                })
+                                .unwrap(),
+                            )
+                        }
+                    }
+                );
+                add_choice!(opts.fade_duration, "Fade time (in seconds)", |opts| {
+                    if let ModuleType::Volume(ref mut opts) = opts {
+                        opts.fade_duration = Duration::from_secs_f64(
+                            utils::get_input("Invalid number", |input| {
+                                input.parse::<f64>().ok().filter(|&secs| secs >= 0.)
+                            })
+                            .unwrap(),
+                        )
+                    }
+                });
+                add_choice!(
+                    opts.idle_brightness,
+                    "Brightness when idle (0.0 to 1.0)",
+                    |opts| {
+                        if let ModuleType::Volume(ref mut opts) = opts {
+                            opts.idle_brightness = utils::get_input("Invalid number", |input| {
+                                input
+                                    .parse::<f32>()
+                                    .ok()
+                                    .filter(|brightness| (0. ..=1.).contains(brightness))
+                            })
+                            .unwrap();
+                        }
+                    }
+                );
+            }
+            ModuleType::MicMute(opts) => {
+                add_choice!(rgb_to_hex(opts.muted_color), "Muted color", |opts| {
+                    if let ModuleType::MicMute(ref mut opts) = opts {
+                        opts.muted_color = utils::get_color_input().unwrap()
+                    }
+                });
+                add_choice!(rgb_to_hex(opts.live_color), "Live color", |opts| {
+                    if let ModuleType::MicMute(ref mut opts) = opts {
+                        opts.live_color = utils::get_color_input().unwrap()
+                    }
+                });
+            }
         };
         (choices_names, choices_handlers)
     }
-    pub(crate) fn all_module_types() -> [ModuleType; 4] {
+    pub(crate) fn all_module_types() -> [ModuleType; 9] {
         [
-            ModuleType::Workspaces,
-            ModuleType::Media,
+            ModuleType::Workspaces(WorkspacesModuleOptions::default()),
+            ModuleType::Media(MediaModuleOptions::default()),
             ModuleType::Starfield(StarfieldModuleOptions::default()),
             ModuleType::Noise(NoiseModuleOptions::default()),
+            ModuleType::BindingMode(BindingModeModuleOptions::default()),
+            ModuleType::ShortcutHints(ShortcutHintsModuleOptions::default()),
+            ModuleType::KeyboardLayout(KeyboardLayoutModuleOptions::default()),
+            ModuleType::Volume(VolumeModuleOptions::default()),
+            ModuleType::MicMute(MicMuteModuleOptions::default()),
         ]
     }
 }
@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+
+use super::utils;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) enum ProgressBarStyle {
+    /// Fills up from the first LED
+    #[default]
+    Fill,
+    /// Only the LED at the current position is lit
+    Cursor,
+    /// Fills up from the middle towards both ends
+    CenterOut,
+    /// Like `Fill`, but with an unlit gap after every `segment_length` LEDs
+    Segmented,
+    /// The LEDs are split into rows, separated by two unbound LEDs, and the same bar is drawn on
+    /// every row
+    Rows,
+}
+
+impl ProgressBarStyle {
+    pub(crate) fn all() -> [ProgressBarStyle; 5] {
+        [
+            ProgressBarStyle::Fill,
+            ProgressBarStyle::Cursor,
+            ProgressBarStyle::CenterOut,
+            ProgressBarStyle::Segmented,
+            ProgressBarStyle::Rows,
+        ]
+    }
+}
+
+/// Shows a fraction on a list of LEDs
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ProgressBar {
+    #[serde(default)]
+    pub(crate) style: ProgressBarStyle,
+    /// Goes from the last LED to the first instead
+    #[serde(default)]
+    pub(crate) reverse: bool,
+    /// The number of LEDs between the gaps of the segmented style
+    #[serde(default = "default_segment_length")]
+    pub(crate) segment_length: u32,
+}
+
+impl Default for ProgressBar {
+    fn default() -> Self {
+        Self {
+            style: ProgressBarStyle::default(),
+            reverse: false,
+            segment_length: default_segment_length(),
+        }
+    }
+}
+
+fn default_segment_length() -> u32 {
+    4
+}
+
+impl ProgressBar {
+    /// The brightness from 0.0 to 1.0 of every bound LED at `progress` (0.0 to 1.0)
+    pub(crate) fn render(&self, leds: &[Option<u32>], progress: f32) -> Vec<(u32, f32)> {
+        let rows = if self.style == ProgressBarStyle::Rows {
+            utils::split_rows(leds)
+        } else {
+            vec![leds.to_vec()]
+        };
+        let mut out = Vec::new();
+        for row in rows {
+            let brightness = self.brightness(progress, row.len() as u32);
+            for (led, brightness) in row.into_iter().zip(brightness) {
+                if let Some(led) = led {
+                    out.push((led, brightness));
+                }
+            }
+        }
+        out
+    }
+
+    /// Only the LEDs that changed since the bar was drawn at `last_progress`. All LEDs if it
+    /// hasn't been drawn before
+    pub(crate) fn render_diff(
+        &self,
+        leds: &[Option<u32>],
+        progress: f32,
+        last_progress: Option<f32>,
+    ) -> Vec<(u32, f32)> {
+        let bar = self.render(leds, progress);
+        let Some(last_progress) = last_progress else {
+            return bar;
+        };
+        let last_bar = self.render(leds, last_progress);
+        bar.into_iter()
+            .zip(last_bar)
+            .filter(|(led, last_led)| led != last_led)
+            .map(|(led, _)| led)
+            .collect()
+    }
+
+    /// The brightness of each position in a row of `num_leds` LEDs
+    fn brightness(&self, progress: f32, num_leds: u32) -> Vec<f32> {
+        let progress = progress.clamp(0., 1.);
+        let mut out = match self.style {
+            ProgressBarStyle::Fill | ProgressBarStyle::Rows => fill(progress, num_leds, false),
+            ProgressBarStyle::Cursor => fill(progress, num_leds, true),
+            ProgressBarStyle::CenterOut => {
+                // Positions are counted outwards from the middle, which is two LEDs wide for an
+                // even number of LEDs
+                let center_left = num_leds.saturating_sub(1) / 2;
+                let center_right = num_leds / 2;
+                let half = fill(progress, num_leds - center_right, false);
+                (0..num_leds)
+                    .map(|i| {
+                        let distance = if i >= center_right {
+                            i - center_right
+                        } else {
+                            center_left - i
+                        };
+                        half[distance as usize]
+                    })
+                    .collect()
+            }
+            ProgressBarStyle::Segmented => {
+                let is_gap = |i: u32| (i + 1).is_multiple_of(self.segment_length.max(1) + 1);
+                let num_filled = (0..num_leds).filter(|&i| !is_gap(i)).count() as u32;
+                let mut bar = fill(progress, num_filled, false).into_iter();
+                (0..num_leds)
+                    .map(|i| {
+                        if is_gap(i) {
+                            0.
+                        } else {
+                            bar.next().unwrap_or(0.)
+                        }
+                    })
+                    .collect()
+            }
+        };
+        if self.reverse {
+            out.reverse();
+        }
+        out
+    }
+}
+
+fn fill(progress: f32, num_leds: u32, only_show_cursor: bool) -> Vec<f32> {
+    if num_leds == 0 {
+        return Vec::new();
+    }
+    // We offset to make the bar start att all off and end at all on. Otherwise it will start at
+    // one LED on.
+    let offset = if !only_show_cursor { 1 } else { 0 };
+    let progress_to_next_led = progress % (1. / (num_leds as f32)) * num_leds as f32;
+    // The LED that is the furthest back in the "cursor"
+    let led_at_back = (progress / (1. / num_leds as f32)).floor() as u32;
+    let mut out = Vec::with_capacity(num_leds as usize);
+    for i in offset..num_leds + offset {
+        out.push(if i < led_at_back {
+            if only_show_cursor {
+                0.
+            } else {
+                1.
+            }
+        } else if i == led_at_back {
+            if only_show_cursor {
+                1. - progress_to_next_led
+            } else {
+                1.
+            }
+        } else if i == led_at_back + 1 {
+            progress_to_next_led
+        } else {
+            0.
+        })
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProgressBar, ProgressBarStyle};
+
+    fn bar(style: ProgressBarStyle, reverse: bool) -> ProgressBar {
+        ProgressBar {
+            style,
+            reverse,
+            segment_length: 2,
+        }
+    }
+
+    #[test]
+    fn test_fill() {
+        let leds = [Some(0), Some(1), Some(2), Some(3)];
+        assert_eq!(
+            bar(ProgressBarStyle::Fill, false).render(&leds, 0.5),
+            vec![(0, 1.), (1, 1.), (2, 0.), (3, 0.)]
+        );
+        assert_eq!(
+            bar(ProgressBarStyle::Fill, true).render(&leds, 0.5),
+            vec![(0, 0.), (1, 0.), (2, 1.), (3, 1.)]
+        );
+        assert_eq!(
+            bar(ProgressBarStyle::Fill, false).render(&leds, 1.),
+            vec![(0, 1.), (1, 1.), (2, 1.), (3, 1.)]
+        );
+    }
+
+    #[test]
+    fn test_center_out() {
+        let leds = [Some(0), Some(1), Some(2), Some(3), Some(4), Some(5)];
+        assert_eq!(
+            bar(ProgressBarStyle::CenterOut, false).render(&leds, 2. / 3.),
+            vec![(0, 0.), (1, 1.), (2, 1.), (3, 1.), (4, 1.), (5, 0.)]
+        );
+    }
+
+    #[test]
+    fn test_segmented() {
+        let leds = [Some(0), Some(1), Some(2), Some(3), Some(4)];
+        assert_eq!(
+            bar(ProgressBarStyle::Segmented, false).render(&leds, 0.75),
+            vec![(0, 1.), (1, 1.), (2, 0.), (3, 1.), (4, 0.)]
+        );
+    }
+
+    #[test]
+    fn test_rows() {
+        let leds = [Some(0), Some(1), None, None, Some(10), Some(11)];
+        assert_eq!(
+            bar(ProgressBarStyle::Rows, false).render(&leds, 0.5),
+            vec![(0, 1.), (1, 0.), (10, 1.), (11, 0.)]
+        );
+    }
+
+    #[test]
+    fn test_render_diff() {
+        let leds = [Some(0), Some(1), Some(2), Some(3)];
+        let progress_bar = bar(ProgressBarStyle::Fill, false);
+        assert_eq!(progress_bar.render_diff(&leds, 0.5, Some(0.5)), vec![]);
+        assert_eq!(
+            progress_bar.render_diff(&leds, 0.75, Some(0.5)),
+            vec![(2, 1.)]
+        );
+    }
+}
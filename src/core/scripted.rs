@@ -0,0 +1,62 @@
+use std::collections::VecDeque;
+use std::future::Future;
+
+use tokio::sync::mpsc::{self, Sender};
+use tokio_util::sync::CancellationToken;
+
+use super::keyboard_controller::KeyboardControllerMessage;
+
+/// Plays back a fixed list of states, one per change. Modules that read from a source, like
+/// workspaces or audio, are tested with this in place of the real one
+pub(crate) struct Scripted<T> {
+    current: T,
+    upcoming: VecDeque<T>,
+}
+
+impl<T> Scripted<T> {
+    /// The first state is there from the start, the others follow one change at a time
+    pub(crate) fn new(states: Vec<T>) -> Self {
+        let mut upcoming = VecDeque::from(states);
+        Self {
+            current: upcoming.pop_front().expect("There has to be a first state"),
+            upcoming,
+        }
+    }
+
+    pub(crate) fn current(&self) -> &T {
+        &self.current
+    }
+
+    /// Moves on to the next state. Never returns once the script has run out, like a source
+    /// where nothing happens anymore
+    pub(crate) async fn next(&mut self) {
+        match self.upcoming.pop_front() {
+            Some(next) => self.current = next,
+            None => std::future::pending().await,
+        }
+    }
+}
+
+/// Runs a module until `done` is true for the messages it has sent, then cancels it. Returns
+/// every message it sent, including those sent before it noticed the cancellation
+pub(crate) async fn run_until<F: Future<Output = ()>>(
+    run: impl FnOnce(CancellationToken, Sender<KeyboardControllerMessage>) -> F,
+    mut done: impl FnMut(&[KeyboardControllerMessage]) -> bool,
+) -> Vec<KeyboardControllerMessage> {
+    let (sender, mut receiver) = mpsc::channel(100);
+    let cancellation_token = CancellationToken::new();
+    let run = run(cancellation_token.clone(), sender);
+    let check = async {
+        let mut messages = Vec::new();
+        while !done(&messages) {
+            messages.push(receiver.recv().await.unwrap());
+        }
+        cancellation_token.cancel();
+        messages
+    };
+    let (_, mut messages) = tokio::join!(run, check);
+    while let Ok(message) = receiver.try_recv() {
+        messages.push(message);
+    }
+    messages
+}
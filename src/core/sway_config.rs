@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+
+use crate::core::key_id::KeyId;
+
+/// A `bindsym` line from the Sway config
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SwayBinding {
+    /// The binding mode the binding is in. Bindings outside of a mode block are in "default"
+    pub(crate) mode: String,
+    /// Normalized with `normalize_modifier`
+    pub(crate) modifiers: Vec<String>,
+    /// The xkb keysym name of the key, like "Return" or "h"
+    pub(crate) key: String,
+    pub(crate) command: String,
+}
+
+/// What a `{` in the config opened, so that we know what the matching `}` closes
+enum Block {
+    Mode(String),
+    /// A `bindsym { ... }` block, where every line is a binding
+    Bindsym,
+    Other,
+}
+
+/// Finds all `bindsym` bindings in the config as returned by Sway's `get_config`. Variables
+/// defined with `set` are substituted. `bindcode` bindings are ignored, since they don't name a
+/// key
+pub(crate) fn parse_bindings(config: &str) -> Vec<SwayBinding> {
+    let mut variables: Vec<(String, String)> = Vec::new();
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut bindings = Vec::new();
+
+    for line in config.replace("\\\n", " ").lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(("set", definition)) = line.split_once(char::is_whitespace) {
+            if let Some((name, value)) = definition.trim().split_once(char::is_whitespace) {
+                let value = substitute_variables(value.trim(), &variables);
+                variables.push((name.to_owned(), value));
+                // Longer names first, so that $mod doesn't replace the start of $modifier
+                variables.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+            }
+            continue;
+        }
+        let line = substitute_variables(line, &variables);
+        let mode = blocks
+            .iter()
+            .rev()
+            .find_map(|block| match block {
+                Block::Mode(mode) => Some(mode.as_str()),
+                _ => None,
+            })
+            .unwrap_or("default")
+            .to_owned();
+
+        if line == "}" {
+            blocks.pop();
+            continue;
+        }
+        if matches!(blocks.last(), Some(Block::Bindsym)) {
+            bindings.extend(parse_binding(&mode, &line));
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("mode") if line.ends_with('{') => {
+                blocks.push(Block::Mode(parse_mode_name(&line).to_owned()));
+            }
+            Some("bindsym") => {
+                let rest = words
+                    .skip_while(|word| word.starts_with("--"))
+                    .collect::<Vec<&str>>()
+                    .join(" ");
+                if rest == "{" {
+                    blocks.push(Block::Bindsym);
+                } else {
+                    bindings.extend(parse_binding(&mode, &rest));
+                }
+            }
+            _ => {
+                if line.ends_with('{') {
+                    blocks.push(Block::Other);
+                }
+            }
+        }
+    }
+    bindings
+}
+
+/// The layout codes of the first `xkb_layout` in the config, like `us` and `se` for
+/// `input type:keyboard xkb_layout us,se`. They are in the same order as the layout names Sway
+/// reports
+pub(crate) fn parse_xkb_layouts(config: &str) -> Vec<String> {
+    config
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .find_map(|line| {
+            let mut words = line.split_whitespace();
+            words.find(|&word| word == "xkb_layout")?;
+            words.next()
+        })
+        .map(|layouts| {
+            layouts
+                .trim_matches('"')
+                .split(',')
+                .map(|layout| layout.trim().to_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The name of the mode a `mode` block opens, like `launch app` for `mode "launch app" {`. The
+/// name is everything between the flags and the `{`, so quoted names can have spaces
+fn parse_mode_name(line: &str) -> &str {
+    let mut rest = line.trim_start_matches("mode").trim_start();
+    while rest.starts_with("--") {
+        rest = rest
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, rest)| rest.trim_start());
+    }
+    rest.trim_end_matches('{').trim().trim_matches('"')
+}
+
+fn substitute_variables(line: &str, variables: &[(String, String)]) -> String {
+    let mut line = line.to_owned();
+    for (name, value) in variables {
+        line = line.replace(name, value);
+    }
+    line
+}
+
+/// Parses the part of a binding after `bindsym` and its flags, like `$mod+Shift+q kill`
+fn parse_binding(mode: &str, binding: &str) -> Option<SwayBinding> {
+    let (combination, command) = binding.trim().split_once(char::is_whitespace)?;
+    let mut keys = combination.split('+').collect::<Vec<&str>>();
+    let key = keys.pop()?;
+    Some(SwayBinding {
+        mode: mode.to_owned(),
+        modifiers: keys.into_iter().map(normalize_modifier).collect(),
+        key: key.to_owned(),
+        command: command.trim().to_owned(),
+    })
+}
+
+/// Sway accepts several names for the same modifier. This maps them to a single one
+pub(crate) fn normalize_modifier(modifier: &str) -> String {
+    match modifier {
+        "Mod4" | "Super" | "Logo" => "Mod4",
+        "Mod1" | "Alt" => "Mod1",
+        "Ctrl" | "Control" => "Control",
+        "Shift" => "Shift",
+        other => other,
+    }
+    .to_owned()
+}
+
+/// Maps an xkb keysym name, as used in `bindsym`, to the key that produces it
+pub(crate) fn keysym_to_key_id(keysym: &str) -> Option<KeyId> {
+    let mut chars = keysym.chars();
+    if let (Some(char), None) = (chars.next(), chars.next()) {
+        return Some(KeyId::Char(char.to_ascii_lowercase()));
+    }
+    if let Some(Ok(number)) = keysym.strip_prefix('F').map(str::parse::<u8>) {
+        return KeyId::function_key(number);
+    }
+    let named_chars: HashMap<&str, char> = HashMap::from([
+        ("space", ' '),
+        ("minus", '-'),
+        ("equal", '='),
+        ("plus", '+'),
+        ("comma", ','),
+        ("period", '.'),
+        ("slash", '/'),
+        ("backslash", '\\'),
+        ("semicolon", ';'),
+        ("apostrophe", '\''),
+        ("grave", '`'),
+        ("bracketleft", '['),
+        ("bracketright", ']'),
+    ]);
+    if let Some(&char) = named_chars.get(keysym.to_lowercase().as_str()) {
+        return Some(KeyId::Char(char));
+    }
+    Some(match keysym {
+        "Return" => KeyId::Enter,
+        "KP_Enter" => KeyId::KeypadEnter,
+        "Escape" => KeyId::Esc,
+        "Tab" => KeyId::Tab,
+        "BackSpace" => KeyId::Backspace,
+        "Delete" => KeyId::Delete,
+        "Insert" => KeyId::Insert,
+        "Home" => KeyId::Home,
+        "End" => KeyId::End,
+        "Prior" | "Page_Up" => KeyId::PageUp,
+        "Next" | "Page_Down" => KeyId::PageDown,
+        "Left" => KeyId::Left,
+        "Right" => KeyId::Right,
+        "Up" => KeyId::Up,
+        "Down" => KeyId::Down,
+        "Print" => KeyId::PrintScreen,
+        "Pause" => KeyId::Pause,
+        "Menu" => KeyId::Menu,
+        "Caps_Lock" => KeyId::CapsLock,
+        "Scroll_Lock" => KeyId::ScrollLock,
+        "Num_Lock" => KeyId::NumLock,
+        "Shift_L" => KeyId::LeftShift,
+        "Shift_R" => KeyId::RightShift,
+        "Control_L" => KeyId::LeftCtrl,
+        "Control_R" => KeyId::RightCtrl,
+        "Alt_L" => KeyId::LeftAlt,
+        "Alt_R" | "ISO_Level3_Shift" => KeyId::RightAlt,
+        "Super_L" => KeyId::LeftSuper,
+        "Super_R" => KeyId::RightSuper,
+        "XF86AudioPlay" | "XF86AudioPause" => KeyId::MediaPlayPause,
+        "XF86AudioStop" => KeyId::MediaStop,
+        "XF86AudioNext" => KeyId::MediaNext,
+        "XF86AudioPrev" => KeyId::MediaPrevious,
+        "XF86AudioMute" => KeyId::Mute,
+        "XF86AudioRaiseVolume" => KeyId::VolumeUp,
+        "XF86AudioLowerVolume" => KeyId::VolumeDown,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::key_id::KeyId;
+
+    use super::{keysym_to_key_id, parse_bindings, parse_xkb_layouts, SwayBinding};
+
+    #[test]
+    fn test_parse_bindings() {
+        let config = "
+set $mod Mod4
+set $modifier Shift
+# bindsym $mod+x exec commented
+bindsym --to-code $mod+Return exec foot
+bindsym $mod+$modifier+q \\
+    kill
+bar {
+    position top
+}
+mode \"resize\" {
+    bindsym Left resize shrink width 10px
+    bindsym Escape mode \"default\"
+}
+bindsym {
+    Ctrl+1 workspace number 1
+}
+mode --pango_markup \"launch app\" {
+    bindsym f exec firefox
+}
+";
+        assert_eq!(
+            parse_bindings(config),
+            vec![
+                SwayBinding {
+                    mode: "default".to_owned(),
+                    modifiers: vec!["Mod4".to_owned()],
+                    key: "Return".to_owned(),
+                    command: "exec foot".to_owned(),
+                },
+                SwayBinding {
+                    mode: "default".to_owned(),
+                    modifiers: vec!["Mod4".to_owned(), "Shift".to_owned()],
+                    key: "q".to_owned(),
+                    command: "kill".to_owned(),
+                },
+                SwayBinding {
+                    mode: "resize".to_owned(),
+                    modifiers: vec![],
+                    key: "Left".to_owned(),
+                    command: "resize shrink width 10px".to_owned(),
+                },
+                SwayBinding {
+                    mode: "resize".to_owned(),
+                    modifiers: vec![],
+                    key: "Escape".to_owned(),
+                    command: "mode \"default\"".to_owned(),
+                },
+                SwayBinding {
+                    mode: "default".to_owned(),
+                    modifiers: vec!["Control".to_owned()],
+                    key: "1".to_owned(),
+                    command: "workspace number 1".to_owned(),
+                },
+                SwayBinding {
+                    mode: "launch app".to_owned(),
+                    modifiers: vec![],
+                    key: "f".to_owned(),
+                    command: "exec firefox".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_xkb_layouts() {
+        let config = "
+# input * xkb_layout de
+input type:keyboard {
+    xkb_layout \"us,se\"
+    xkb_options grp:win_space_toggle
+}
+input 1:1:keyboard xkb_layout fi
+";
+        assert_eq!(parse_xkb_layouts(config), vec!["us", "se"]);
+        assert_eq!(
+            parse_xkb_layouts("input * xkb_variant nodeadkeys"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_keysym_to_key_id() {
+        assert_eq!(keysym_to_key_id("Q"), Some(KeyId::Char('q')));
+        assert_eq!(keysym_to_key_id("F11"), Some(KeyId::F(11)));
+        assert_eq!(keysym_to_key_id("minus"), Some(KeyId::Char('-')));
+        assert_eq!(keysym_to_key_id("Return"), Some(KeyId::Enter));
+        assert_eq!(
+            keysym_to_key_id("XF86AudioPlay"),
+            Some(KeyId::MediaPlayPause)
+        );
+        assert_eq!(keysym_to_key_id("XF86Launch5"), None);
+    }
+}
@@ -0,0 +1,172 @@
+use std::ffi::OsString;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use async_io::Async;
+use futures_util::stream::StreamExt;
+use swayipc_async::{Connection, Error, Event, EventStream, EventType};
+
+use super::constants;
+
+/// What `SwayIpc::next_event` gives
+pub(crate) enum SwayMessage {
+    Event(Box<Event>),
+    /// The event stream closed, because Sway exited or restarted, and has been opened again.
+    /// Events may have been missed in between, so anything that was queried is out of date
+    Reconnected,
+}
+
+/// A subscription to Sway or i3 events, and a connection for queries. Subscribing consumes the
+/// connection, so the queries need one of their own
+pub(crate) struct SwayIpc {
+    events: EventStream,
+    pub(crate) connection: Connection,
+    socket_path: Option<OsString>,
+    event_types: Vec<EventType>,
+}
+
+impl SwayIpc {
+    /// Without a socket path, swayipc looks for one itself
+    pub(crate) async fn connect(
+        socket_path: Option<OsString>,
+        event_types: Vec<EventType>,
+    ) -> anyhow::Result<Self> {
+        let (events, connection) = Self::open(socket_path.as_ref(), &event_types).await?;
+        Ok(Self {
+            events,
+            connection,
+            socket_path,
+            event_types,
+        })
+    }
+
+    async fn open(
+        socket_path: Option<&OsString>,
+        event_types: &[EventType],
+    ) -> anyhow::Result<(EventStream, Connection)> {
+        let events = Self::connect_to(socket_path)
+            .await?
+            .subscribe(event_types)
+            .await?;
+        Ok((events, Self::connect_to(socket_path).await?))
+    }
+
+    async fn connect_to(socket_path: Option<&OsString>) -> anyhow::Result<Connection> {
+        Ok(match socket_path {
+            Some(socket_path) => Connection::from(Async::<UnixStream>::connect(socket_path).await?),
+            None => Connection::new().await?,
+        })
+    }
+
+    /// Waits for the next event. Events that can't be decoded are skipped. If the socket fails,
+    /// this keeps trying to connect again, waiting longer after every failure
+    pub(crate) async fn next_event(&mut self) -> SwayMessage {
+        loop {
+            match self.events.next().await {
+                Some(Ok(event)) => return SwayMessage::Event(Box::new(event)),
+                Some(Err(Error::SerdeJson(_) | Error::UnimplementedEvent(..))) => continue,
+                // The stream never ends by itself. Once the socket is closed, every read fails
+                // with an I/O error instead, so those are what a disconnect looks like
+                Some(Err(_)) | None => {
+                    self.reconnect().await;
+                    return SwayMessage::Reconnected;
+                }
+            }
+        }
+    }
+
+    async fn reconnect(&mut self) {
+        let mut attempt = 0;
+        loop {
+            tokio::time::sleep(reconnect_delay(attempt)).await;
+            match Self::open(self.socket_path.as_ref(), &self.event_types).await {
+                Ok((events, connection)) => {
+                    self.events = events;
+                    self.connection = connection;
+                    return;
+                }
+                Err(err) => {
+                    if attempt == 0 {
+                        eprintln!("Lost the connection to Sway. Trying again. {}", err);
+                    }
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+fn reconnect_delay(attempt: u32) -> Duration {
+    (constants::SWAY_RECONNECT_DELAY * 2u32.saturating_pow(attempt))
+        .min(constants::SWAY_MAX_RECONNECT_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::time::Duration;
+
+    use swayipc_async::EventType;
+
+    use super::{reconnect_delay, SwayIpc, SwayMessage};
+
+    /// Sends a message the way Sway does, with the i3-ipc header
+    fn send(stream: &mut UnixStream, message_type: u32, payload: &[u8]) {
+        let mut message = b"i3-ipc".to_vec();
+        message.extend((payload.len() as u32).to_ne_bytes());
+        message.extend(message_type.to_ne_bytes());
+        message.extend(payload);
+        stream.write_all(&message).unwrap();
+    }
+
+    /// Accepts the two connections `SwayIpc` opens, and answers the subscription on the first
+    fn accept(listener: &UnixListener) -> (UnixStream, UnixStream) {
+        let (mut events, _) = listener.accept().unwrap();
+        let mut header = [0; 14];
+        events.read_exact(&mut header).unwrap();
+        let length = u32::from_ne_bytes(header[6..10].try_into().unwrap());
+        events.read_exact(&mut vec![0; length as usize]).unwrap();
+        send(&mut events, 2, br#"{"success": true}"#);
+        let (queries, _) = listener.accept().unwrap();
+        (events, queries)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reconnects_when_closed() {
+        let socket_path = std::env::temp_dir().join(format!(
+            "keyboard-indicators-test-sway-{}.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut events, _queries) = accept(&listener);
+            // An event this version of swayipc doesn't know is skipped
+            send(&mut events, 0x80000000 | 99, b"{}");
+            drop(events);
+            accept(&listener)
+        });
+
+        let mut sway = SwayIpc::connect(Some(socket_path.clone().into()), vec![EventType::Mode])
+            .await
+            .unwrap();
+        // Spawned, so that a loop that never yields can't stop the timeout
+        let next = tokio::spawn(async move { sway.next_event().await });
+        let message = tokio::time::timeout(Duration::from_secs(5), next)
+            .await
+            .expect("next_event didn't return after the socket closed")
+            .unwrap();
+        assert!(matches!(message, SwayMessage::Reconnected));
+        server.join().unwrap();
+        std::fs::remove_file(&socket_path).unwrap();
+    }
+
+    #[test]
+    fn test_reconnect_delay() {
+        assert_eq!(reconnect_delay(0), Duration::from_millis(500));
+        assert_eq!(reconnect_delay(2), Duration::from_secs(2));
+        assert_eq!(reconnect_delay(10), Duration::from_secs(30));
+        assert_eq!(reconnect_delay(100), Duration::from_secs(30));
+    }
+}
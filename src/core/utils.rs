@@ -1,118 +1,43 @@
 use std::io::{self, BufRead, Read, Write};
 use std::path::PathBuf;
-use std::process::Stdio;
 
 use anyhow::bail;
 use anyhow::Result;
 use clap::ArgMatches;
 use crossterm::event::{
-    DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
-    PushKeyboardEnhancementFlags,
+    DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+    KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
 };
 use rgb::{ComponentMap, RGB, RGB8, RGBA8};
-use tokio::process::{self, ChildStdout};
 use tokio::sync::mpsc::Sender;
 
 use super::config_manager::Configuration;
+use super::key_id::KeyId;
 use super::keyboard_controller::{KeyboardController, KeyboardControllerMessage};
 use super::module::Module;
 
-pub(crate) fn run_command_async(command: &str) -> Option<ChildStdout> {
-    let mut command_array = command.split(' ');
-    if command_array.clone().count() == 0 {
-        return None;
-    }
-    println!("Running command: {}", command);
-
-    let mut cmd = process::Command::new(command_array.next().unwrap())
-        .stdout(Stdio::piped())
-        .args(command_array)
-        .spawn()
-        .unwrap();
-    cmd.stdout.take()
-}
-
-pub(crate) fn run_command(command: &str) -> Option<String> {
-    let mut command_array = command.split(' ');
-    if command_array.clone().count() == 0 {
-        return Default::default();
-    }
-
-    let cmd = std::process::Command::new(command_array.next().unwrap())
-        .stdout(Stdio::piped())
-        .args(command_array)
-        .output()
-        .unwrap();
-
-    String::from_utf8(cmd.stdout)
-        .ok()
-        .map(|e| e.trim().to_owned())
-}
-
 pub(crate) fn overlay(color1: RGBA8, color2: RGB8) -> RGB8 {
     (color1
         .rgb()
         .map(|comp| comp as f32 * color1.a as f32 / 255.)
         + color2.map(|comp| comp as f32 * (1. - color1.a as f32 / 255.)))
-        .map(|comp| comp as u8)
+    .map(|comp| comp as u8)
 }
 
-pub(crate) fn progress_bar(progress: f32, num_leds: u32, only_show_cursor: bool) -> Vec<f32> {
-    // We offset to make the bar start att all off and end at all on. Otherwise it will start at
-    // one LED on.
-    let offset = if !only_show_cursor { 1 } else { 0 };
-    let progress_to_next_led = progress % (1. / (num_leds as f32)) * num_leds as f32;
-    // The LED that is the furthest back in the "cursor"
-    let led_at_back = (progress / (1. / num_leds as f32)).floor() as u32;
-    let mut out = Vec::with_capacity(num_leds as usize);
-    for i in offset..num_leds + offset {
-        out.push(if i < led_at_back {
-            if only_show_cursor {
-                0.
-            } else {
-                1.
-            }
-        } else if i == led_at_back {
-            if only_show_cursor {
-                1. - progress_to_next_led
-            } else {
-                1.
-            }
-        } else if i == led_at_back + 1 {
-            progress_to_next_led
+/// Splits the LEDs of a module into rows. Two unbound LEDs (None) back to back are treated as a
+/// line break
+pub(crate) fn split_rows(leds: &[Option<u32>]) -> Vec<Vec<Option<u32>>> {
+    let mut rows = vec![Vec::new()];
+    for led in leds {
+        let row = rows.last_mut().unwrap();
+        if led.is_none() && row.last() == Some(&None) {
+            row.pop();
+            rows.push(Vec::new());
         } else {
-            0.
-        })
-    }
-    out
-}
-
-/// Returns a vector with "orders". Each order is a tuple where the first element is the LED index
-/// and the second is the brightness it should have as a float from 0.0 to 1.0
-pub(crate) fn progress_bar_diff(
-    progress: f32,
-    last_progress: Option<f32>,
-    num_leds: u32,
-    only_show_cursor: bool,
-) -> Vec<(u32, f32)> {
-    let last_progress_bar =
-        last_progress.map(|last_progress| progress_bar(last_progress, num_leds, only_show_cursor));
-    let mut out = Vec::new();
-    let progress_bar = progress_bar(progress, num_leds, only_show_cursor);
-    if let Some(last_progress_bar) = last_progress_bar {
-        for (i, &led_value) in progress_bar.iter().enumerate() {
-            if last_progress_bar[i] != led_value {
-                out.push((i as u32, led_value));
-            }
+            row.push(*led);
         }
-    } else {
-        out = progress_bar
-            .iter()
-            .enumerate()
-            .map(|(i, led_value)| (i as u32, *led_value))
-            .collect();
     }
-    out
+    rows
 }
 
 pub(crate) fn prepare_terminal_event_capture() -> Result<()> {
@@ -133,7 +58,7 @@ pub(crate) fn prepare_terminal_event_capture() -> Result<()> {
                  | KeyboardEnhancementFlags::REPORT_EVENT_TYPES,
             )
         )
-            .unwrap();
+        .unwrap();
     }
     crossterm::execute!(
         stdout,
@@ -141,10 +66,32 @@ pub(crate) fn prepare_terminal_event_capture() -> Result<()> {
         // EnableFocusChange,
         EnableMouseCapture,
     )
-        .unwrap();
+    .unwrap();
     Ok(())
 }
 
+/// Waits for a key press. Escape gives None, so the user can choose no key
+pub(crate) fn read_key() -> Result<Option<KeyId>> {
+    prepare_terminal_event_capture()?;
+    let key = loop {
+        let Event::Key(event) = crossterm::event::read()? else {
+            continue;
+        };
+        if event.kind != KeyEventKind::Press {
+            continue;
+        }
+        if event.modifiers.intersects(KeyModifiers::CONTROL) && event.code == KeyCode::Char('c') {
+            default_terminal_settings()?;
+            bail!("Interrupted by user");
+        }
+        if let Some(key) = KeyId::from_key_event(&event) {
+            break key;
+        }
+    };
+    default_terminal_settings()?;
+    Ok((key != KeyId::Esc).then_some(key))
+}
+
 pub(crate) fn default_terminal_settings() -> Result<()> {
     let supports_keyboard_enhancement = matches!(
         crossterm::terminal::supports_keyboard_enhancement(),
@@ -162,7 +109,7 @@ pub(crate) fn default_terminal_settings() -> Result<()> {
         // EnableFocusChange,
         DisableMouseCapture,
     )
-        .unwrap();
+    .unwrap();
     Ok(())
 }
 
@@ -337,10 +284,10 @@ where
 pub(crate) fn parse_rgb(input: &str) -> Result<RGB8> {
     fn split_decimal_input(input: &str) -> Option<(&str, &str, &str)> {
         let parts: Vec<&str> = input
-            .split(|c| c == ',' || c == ' ')
+            .split([',', ' '])
             .filter(|&part| !part.is_empty())
             .collect();
-        match (parts.get(0), parts.get(1), parts.get(2), parts.get(3)) {
+        match (parts.first(), parts.get(1), parts.get(2), parts.get(3)) {
             (Some(&r), Some(&g), Some(&b), None) => Some((r, g, b)),
             _ => None,
         }
@@ -405,7 +352,7 @@ mod tests {
 
     use rgb::RGB;
 
-    use crate::core::utils::{color_list, compute_light_curve};
+    use crate::core::utils::{color_list, compute_light_curve, split_rows};
 
     #[test]
     fn test_color_list() {
@@ -422,4 +369,16 @@ mod tests {
             assert_eq!(compute_light_curve(k as f64, 255), 255)
         }
     }
+
+    #[test]
+    fn test_split_rows() {
+        assert_eq!(
+            split_rows(&[Some(1), Some(2)]),
+            vec![vec![Some(1), Some(2)]]
+        );
+        assert_eq!(
+            split_rows(&[Some(1), None, Some(2), None, None, None, Some(3)]),
+            vec![vec![Some(1), None, Some(2)], vec![None, Some(3)]]
+        );
+    }
 }
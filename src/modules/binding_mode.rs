@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet};
+
+use openrgb::data::Color;
+use rgb::RGB8;
+use serde::{Deserialize, Serialize};
+use swayipc_async::{Connection, Event, EventType};
+use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+use crate::core::key_id::KeyId;
+use crate::core::keyboard_controller::{KeyboardController, KeyboardControllerMessage};
+use crate::core::sway_ipc::{SwayIpc, SwayMessage};
+use crate::core::{constants, sway_config};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct BindingModeModuleOptions {
+    /// Colors for binding modes, keyed by the name of the mode
+    #[serde(default)]
+    pub(crate) mode_colors: HashMap<String, RGB8>,
+    /// The color of modes that aren't in `mode_colors`
+    #[serde(default = "default_color")]
+    pub(crate) default_color: RGB8,
+    /// Only light the keys of the module that have a binding in the active mode
+    #[serde(default)]
+    pub(crate) only_bound_keys: bool,
+}
+
+impl Default for BindingModeModuleOptions {
+    fn default() -> Self {
+        Self {
+            mode_colors: HashMap::new(),
+            default_color: default_color(),
+            only_bound_keys: false,
+        }
+    }
+}
+
+fn default_color() -> RGB8 {
+    constants::DEFAULT_BINDING_MODE_COLOR
+}
+
+pub(crate) struct BindingModeModule {}
+
+impl BindingModeModule {
+    pub fn run(
+        task_tracker: &TaskTracker,
+        cancellation_token: CancellationToken,
+        mut sender: Sender<KeyboardControllerMessage>,
+        module_leds: Vec<Option<u32>>,
+        options: BindingModeModuleOptions,
+        key_led_map: HashMap<KeyId, u32>,
+    ) {
+        task_tracker.spawn(async move {
+            let mut sway = match SwayIpc::connect(None, vec![EventType::Mode]).await {
+                Ok(sway) => sway,
+                Err(err) => {
+                    eprintln!("Failed to connect to Sway socket. {}", err);
+                    return;
+                }
+            };
+            let bound_leds = if options.only_bound_keys {
+                match Self::get_bound_leds(&mut sway.connection, &key_led_map).await {
+                    Ok(bound_leds) => bound_leds,
+                    Err(err) => {
+                        eprintln!("Failed to read bindings from the Sway config. {}", err);
+                        HashMap::new()
+                    }
+                }
+            } else {
+                HashMap::new()
+            };
+            if let Ok(binding_modes) = sway.connection.get_binding_modes().await {
+                for mode in options.mode_colors.keys() {
+                    if !binding_modes.contains(mode) {
+                        eprintln!(
+                            "There is no binding mode called {} in the Sway config",
+                            mode
+                        );
+                    }
+                }
+            }
+            // Starts out as if Sway had just been connected to, so the mode is queried
+            let mut message = SwayMessage::Reconnected;
+            loop {
+                let mode = match message {
+                    SwayMessage::Event(event) => match *event {
+                        Event::Mode(mode_event) => Some(mode_event.change),
+                        _ => None,
+                    },
+                    SwayMessage::Reconnected => Some(
+                        sway.connection
+                            .get_binding_state()
+                            .await
+                            .unwrap_or_else(|_| "default".to_owned()),
+                    ),
+                };
+                if let Some(mode) = mode {
+                    let render_output =
+                        Self::render(&mut sender, &module_leds, &options, &bound_leds, &mode).await;
+                    if let Err(err) = render_output {
+                        eprintln!("{}", err)
+                    };
+                }
+                message = tokio::select! {
+                    biased;
+                    _ = cancellation_token.cancelled() => {
+                        break;
+                    }
+                    message = sway.next_event() => message,
+                };
+            }
+        });
+    }
+}
+
+impl BindingModeModule {
+    /// The LEDs of the keys that have a binding, for each binding mode
+    async fn get_bound_leds(
+        connection: &mut Connection,
+        key_led_map: &HashMap<KeyId, u32>,
+    ) -> anyhow::Result<HashMap<String, HashSet<u32>>> {
+        let config = connection.get_config().await?.config;
+        let mut bound_leds: HashMap<String, HashSet<u32>> = HashMap::new();
+        for binding in sway_config::parse_bindings(&config) {
+            let Some(led) =
+                sway_config::keysym_to_key_id(&binding.key).and_then(|key| key_led_map.get(&key))
+            else {
+                continue;
+            };
+            bound_leds.entry(binding.mode).or_default().insert(*led);
+        }
+        Ok(bound_leds)
+    }
+
+    async fn render(
+        sender: &mut Sender<KeyboardControllerMessage>,
+        module_leds: &[Option<u32>],
+        options: &BindingModeModuleOptions,
+        bound_leds: &HashMap<String, HashSet<u32>>,
+        mode: &str,
+    ) -> anyhow::Result<()> {
+        let mode_color = *options
+            .mode_colors
+            .get(mode)
+            .unwrap_or(&options.default_color);
+        for &led in module_leds.iter().flatten() {
+            let lit = mode != "default"
+                && (!options.only_bound_keys
+                    || bound_leds
+                        .get(mode)
+                        .is_some_and(|bound_leds| bound_leds.contains(&led)));
+            let color = if lit { mode_color } else { Color::new(0, 0, 0) };
+            KeyboardController::update_led(sender, led, color).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+
+use rgb::RGB8;
+use serde::{Deserialize, Serialize};
+use swayipc_async::{Connection, Event, EventType, Input, InputChange};
+use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+use crate::core::keyboard_controller::{KeyboardController, KeyboardControllerMessage};
+use crate::core::sway_ipc::{SwayIpc, SwayMessage};
+use crate::core::{constants, sway_config};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct KeyboardLayoutModuleOptions {
+    /// Colors keyed by the layout name as Sway reports it, like "English (US)" or "Swedish", or
+    /// by its code in the `xkb_layout` of the Sway config, like "us" or "se". See
+    /// `xkb_layout_names` in `swaymsg -t get_inputs`
+    #[serde(default)]
+    pub(crate) layout_colors: HashMap<String, RGB8>,
+    /// The color of layouts that aren't in `layout_colors`
+    #[serde(default = "default_color")]
+    pub(crate) default_color: RGB8,
+    /// Only follow the keyboard with this identifier, like "1:1:AT_Translated_Set_2_keyboard".
+    /// Every keyboard is followed if there is none, which flickers if they have different layouts
+    #[serde(default)]
+    pub(crate) identifier: Option<String>,
+}
+
+impl Default for KeyboardLayoutModuleOptions {
+    fn default() -> Self {
+        Self {
+            layout_colors: HashMap::new(),
+            default_color: default_color(),
+            identifier: None,
+        }
+    }
+}
+
+fn default_color() -> RGB8 {
+    constants::DEFAULT_KEYBOARD_LAYOUT_COLOR
+}
+
+/// The active layout of a keyboard, by both names it can be given in `layout_colors`
+#[derive(Debug, PartialEq)]
+struct Layout {
+    name: Option<String>,
+    code: Option<String>,
+}
+
+pub(crate) struct KeyboardLayoutModule {}
+
+impl KeyboardLayoutModule {
+    pub fn run(
+        task_tracker: &TaskTracker,
+        cancellation_token: CancellationToken,
+        mut sender: Sender<KeyboardControllerMessage>,
+        module_leds: Vec<Option<u32>>,
+        options: KeyboardLayoutModuleOptions,
+    ) {
+        task_tracker.spawn(async move {
+            let mut sway = match SwayIpc::connect(None, vec![EventType::Input]).await {
+                Ok(sway) => sway,
+                Err(err) => {
+                    eprintln!("Failed to connect to Sway socket. {}", err);
+                    return;
+                }
+            };
+            // The layout codes from the config, in the same order as Sway's layout names
+            let mut layout_codes = Vec::new();
+            let mut last_color = None;
+            // Starts out as if Sway had just been connected to, so the layout is queried
+            let mut message = SwayMessage::Reconnected;
+            loop {
+                let layout = match message {
+                    SwayMessage::Event(event) => match *event {
+                        Event::Input(input_event)
+                            if matches!(
+                                input_event.change,
+                                InputChange::XkbLayout | InputChange::XkbKeymap
+                            ) =>
+                        {
+                            if matches!(input_event.change, InputChange::XkbKeymap) {
+                                layout_codes = Self::layout_codes(&mut sway.connection).await;
+                            }
+                            active_layout(&input_event.input, &layout_codes, &options)
+                        }
+                        _ => None,
+                    },
+                    SwayMessage::Reconnected => {
+                        layout_codes = Self::layout_codes(&mut sway.connection).await;
+                        Self::current_layout(&mut sway.connection, &layout_codes, &options).await
+                    }
+                };
+                if let Some(layout) = layout {
+                    let color = layout_color(&layout, &options);
+                    if last_color != Some(color) {
+                        match Self::render(&mut sender, &module_leds, color).await {
+                            Ok(()) => last_color = Some(color),
+                            Err(err) => eprintln!("{}", err),
+                        }
+                    }
+                }
+                message = tokio::select! {
+                    biased;
+                    _ = cancellation_token.cancelled() => {
+                        break;
+                    }
+                    message = sway.next_event() => message,
+                };
+            }
+        });
+    }
+
+    async fn current_layout(
+        connection: &mut Connection,
+        layout_codes: &[String],
+        options: &KeyboardLayoutModuleOptions,
+    ) -> Option<Layout> {
+        connection.get_inputs().await.ok().and_then(|inputs| {
+            inputs
+                .iter()
+                .find_map(|input| active_layout(input, layout_codes, options))
+        })
+    }
+
+    async fn layout_codes(connection: &mut Connection) -> Vec<String> {
+        match connection.get_config().await {
+            Ok(config) => sway_config::parse_xkb_layouts(&config.config),
+            Err(err) => {
+                eprintln!("Failed to read the Sway config. {}", err);
+                Vec::new()
+            }
+        }
+    }
+
+    async fn render(
+        sender: &mut Sender<KeyboardControllerMessage>,
+        module_leds: &[Option<u32>],
+        color: RGB8,
+    ) -> anyhow::Result<()> {
+        for led in module_leds.iter().flatten() {
+            KeyboardController::update_led(sender, *led, color).await?;
+        }
+        Ok(())
+    }
+}
+
+/// The layout of `input`, if it is a keyboard that is followed. Other inputs are skipped, so
+/// that devices like virtual keyboards don't make the color flicker
+fn active_layout(
+    input: &Input,
+    layout_codes: &[String],
+    options: &KeyboardLayoutModuleOptions,
+) -> Option<Layout> {
+    if input.input_type != "keyboard"
+        || options
+            .identifier
+            .as_ref()
+            .is_some_and(|identifier| *identifier != input.identifier)
+    {
+        return None;
+    }
+    let index = input
+        .xkb_active_layout_index
+        .and_then(|index| usize::try_from(index).ok());
+    let name = input
+        .xkb_active_layout_name
+        .clone()
+        .or_else(|| index.and_then(|index| input.xkb_layout_names.get(index).cloned()));
+    let code = index.and_then(|index| layout_codes.get(index).cloned());
+    (name.is_some() || code.is_some()).then_some(Layout { name, code })
+}
+
+fn layout_color(layout: &Layout, options: &KeyboardLayoutModuleOptions) -> RGB8 {
+    [&layout.name, &layout.code]
+        .into_iter()
+        .flatten()
+        .find_map(|layout| options.layout_colors.get(layout))
+        .copied()
+        .unwrap_or(options.default_color)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rgb::RGB8;
+    use swayipc_async::Input;
+
+    use super::{active_layout, layout_color, KeyboardLayoutModuleOptions, Layout};
+
+    fn input(identifier: &str, input_type: &str, index: i32) -> Input {
+        let names = ["English (US)", "Swedish"];
+        serde_json::from_value(serde_json::json!({
+            "identifier": identifier,
+            "name": identifier,
+            "vendor": 1,
+            "product": 1,
+            "type": input_type,
+            "xkb_active_layout_name": names[index as usize],
+            "xkb_layout_names": names,
+            "xkb_active_layout_index": index,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_active_layout() {
+        let codes = ["us".to_owned(), "se".to_owned()];
+        let options = KeyboardLayoutModuleOptions {
+            identifier: Some("1:1:keyboard".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            active_layout(&input("1:1:keyboard", "keyboard", 1), &codes, &options),
+            Some(Layout {
+                name: Some("Swedish".to_owned()),
+                code: Some("se".to_owned()),
+            })
+        );
+        assert_eq!(
+            active_layout(&input("2:2:virtual", "keyboard", 0), &codes, &options),
+            None
+        );
+        assert_eq!(
+            active_layout(
+                &input("3:3:mouse", "pointer", 0),
+                &codes,
+                &KeyboardLayoutModuleOptions::default()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_layout_color() {
+        let red = RGB8::new(255, 0, 0);
+        let blue = RGB8::new(0, 0, 255);
+        let options = KeyboardLayoutModuleOptions {
+            layout_colors: HashMap::from([
+                ("se".to_owned(), red),
+                ("English (US)".to_owned(), blue),
+            ]),
+            ..Default::default()
+        };
+        let layout = |name: &str, code: &str| Layout {
+            name: Some(name.to_owned()),
+            code: Some(code.to_owned()),
+        };
+        assert_eq!(layout_color(&layout("Swedish", "se"), &options), red);
+        assert_eq!(layout_color(&layout("English (US)", "us"), &options), blue);
+        assert_eq!(
+            layout_color(&layout("German", "de"), &options),
+            options.default_color
+        );
+    }
+}
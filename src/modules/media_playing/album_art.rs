@@ -0,0 +1,110 @@
+use anyhow::{bail, Context};
+use image::RgbImage;
+use rgb::RGB8;
+use url::Url;
+
+/// Images are scaled down to at most this size before picking a color
+const THUMBNAIL_SIZE: u32 = 64;
+/// The number of hue ranges that pixels are grouped into
+const HUE_BUCKETS: usize = 12;
+/// Pixels that are less saturated or darker than this don't count as vibrant
+const MIN_SATURATION: f32 = 0.25;
+const MIN_VALUE: f32 = 0.25;
+
+/// Loads the album art at `art_url` and picks its most vibrant color. Only local `file://` URLs
+/// are supported, which is what most players use for art they have downloaded. Art that is only
+/// online, like the `https://` URLs of browsers and some streaming apps, isn't downloaded
+pub(crate) async fn album_art_color(art_url: &str) -> anyhow::Result<RGB8> {
+    let url = Url::parse(art_url).with_context(|| format!("Invalid art URL {}", art_url))?;
+    if url.scheme() != "file" {
+        bail!(
+            "Album art at {} isn't a local file. Only local album art is supported",
+            art_url
+        );
+    }
+    let Ok(path) = url.to_file_path() else {
+        bail!("Invalid art URL {}", art_url);
+    };
+    // Decoding takes a while for large images
+    let image = tokio::task::spawn_blocking(move || image::open(path))
+        .await?
+        .with_context(|| format!("Failed to load album art from {}", art_url))?;
+    Ok(vibrant_color(
+        &image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgb8(),
+    ))
+}
+
+/// The average color of the hue range with the most saturated and bright pixels. Images without
+/// any such pixels, like black and white ones, give their average color instead
+pub(crate) fn vibrant_color(image: &RgbImage) -> RGB8 {
+    let mut buckets = [(0_f32, [0_f32; 3]); HUE_BUCKETS];
+    let mut sum = [0_f32; 3];
+    for pixel in image.pixels() {
+        let rgb = pixel.0.map(|comp| comp as f32);
+        for (sum, comp) in sum.iter_mut().zip(rgb) {
+            *sum += comp;
+        }
+        let (hue, saturation, value) = rgb_to_hsv(rgb);
+        if saturation < MIN_SATURATION || value < MIN_VALUE {
+            continue;
+        }
+        let weight = saturation * value;
+        let bucket = &mut buckets[(hue / 360. * HUE_BUCKETS as f32) as usize % HUE_BUCKETS];
+        bucket.0 += weight;
+        for (sum, comp) in bucket.1.iter_mut().zip(rgb) {
+            *sum += comp * weight;
+        }
+    }
+
+    let (weight, weighted_sum) = buckets
+        .into_iter()
+        .max_by(|bucket1, bucket2| bucket1.0.total_cmp(&bucket2.0))
+        .unwrap();
+    let (weight, sum) = if weight > 0. {
+        (weight, weighted_sum)
+    } else {
+        ((image.width() * image.height()).max(1) as f32, sum)
+    };
+    let [r, g, b] = sum.map(|comp| (comp / weight).round() as u8);
+    RGB8::new(r, g, b)
+}
+
+/// Hue in degrees, saturation and value from 0.0 to 1.0
+fn rgb_to_hsv([r, g, b]: [f32; 3]) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let hue = if delta == 0. {
+        0.
+    } else if max == r {
+        60. * ((g - b) / delta).rem_euclid(6.)
+    } else if max == g {
+        60. * ((b - r) / delta + 2.)
+    } else {
+        60. * ((r - g) / delta + 4.)
+    };
+    let saturation = if max == 0. { 0. } else { delta / max };
+    (hue, saturation, max / 255.)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+    use rgb::RGB8;
+
+    use super::vibrant_color;
+
+    #[test]
+    fn test_vibrant_color() {
+        // Mostly gray with a smaller red part and an even smaller blue one
+        let image = RgbImage::from_fn(10, 10, |x, _| match x {
+            0..=5 => Rgb([128, 128, 128]),
+            6..=8 => Rgb([200, 20, 20]),
+            _ => Rgb([20, 20, 200]),
+        });
+        assert_eq!(vibrant_color(&image), RGB8::new(200, 20, 20));
+
+        let gray = RgbImage::from_fn(2, 1, |x, _| Rgb([if x == 0 { 100 } else { 200 }; 3]));
+        assert_eq!(vibrant_color(&gray), RGB8::new(150, 150, 150));
+    }
+}
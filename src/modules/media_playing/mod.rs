@@ -0,0 +1,690 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use openrgb::data::Color;
+use regex::Regex;
+use rgb::{ComponentMap, RGB8};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+use crate::core::constants;
+use crate::core::key_id::KeyId;
+use crate::core::keyboard_controller::{KeyboardController, KeyboardControllerMessage};
+use crate::core::progress_bar::ProgressBar;
+
+use self::mpris::{LoopStatus, Mpris, PlaybackStatus, PlayerState, PLAYER_BUS_NAME_PREFIX};
+
+mod album_art;
+mod mpris;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct MediaModuleOptions {
+    #[serde(default)]
+    pub(crate) player_selection: PlayerSelection,
+    /// Checked in order. The first rule that matches a player decides its color
+    #[serde(default = "default_color_rules")]
+    pub(crate) color_rules: Vec<MediaColorRule>,
+    #[serde(default = "default_paused_color")]
+    pub(crate) paused_color: RGB8,
+    /// The color of players that no rule matches
+    #[serde(default = "default_color")]
+    pub(crate) default_color: RGB8,
+    /// Use the most vibrant color of the album art while it is playing, if the player has art
+    /// saved locally. Art that is only online is not downloaded, so those players keep their
+    /// usual color
+    #[serde(default)]
+    pub(crate) album_art_color: bool,
+    #[serde(default)]
+    pub(crate) status_keys: MediaStatusKeys,
+    /// The color of status keys that are on
+    #[serde(default = "default_status_color")]
+    pub(crate) status_color: RGB8,
+    /// The color of the loop status key while looping a single track. `status_color` is used
+    /// while looping the playlist
+    #[serde(default = "default_loop_track_color")]
+    pub(crate) loop_track_color: RGB8,
+    #[serde(default)]
+    pub(crate) progress_bar: ProgressBar,
+}
+
+impl Default for MediaModuleOptions {
+    fn default() -> Self {
+        Self {
+            player_selection: PlayerSelection::default(),
+            color_rules: default_color_rules(),
+            paused_color: default_paused_color(),
+            default_color: default_color(),
+            album_art_color: false,
+            status_keys: MediaStatusKeys::default(),
+            status_color: default_status_color(),
+            loop_track_color: default_loop_track_color(),
+            progress_bar: ProgressBar::default(),
+        }
+    }
+}
+
+fn default_color_rules() -> Vec<MediaColorRule> {
+    vec![
+        MediaColorRule {
+            field: MediaField::BusName,
+            pattern: r"\.spotify$".to_owned(),
+            color: constants::SPOTIFY_MEDIA_PLAYING_COLOR,
+        },
+        MediaColorRule {
+            field: MediaField::Title,
+            pattern: "(?i)netflix".to_owned(),
+            color: constants::NETFLIX_MEDIA_PLAYING_COLOR,
+        },
+    ]
+}
+
+fn default_paused_color() -> RGB8 {
+    constants::PAUSED_MEDIA_PLAYING_COLOR
+}
+
+fn default_color() -> RGB8 {
+    constants::DEFAULT_MEDIA_PLAYING_COLOR
+}
+
+fn default_status_color() -> RGB8 {
+    constants::MEDIA_STATUS_COLOR
+}
+
+fn default_loop_track_color() -> RGB8 {
+    constants::MEDIA_LOOP_TRACK_COLOR
+}
+
+/// Keys that light up to show the state of the player whose progress is shown, like the media
+/// keys. They are left out of the progress bar if they are also part of the module LEDs
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct MediaStatusKeys {
+    /// Lit while shuffle is on
+    pub(crate) shuffle: Option<KeyId>,
+    /// Lit while looping the track or the playlist, in different colors
+    pub(crate) loop_status: Option<KeyId>,
+    /// Lit while the player isn't playing at normal speed
+    pub(crate) rate: Option<KeyId>,
+    /// Lit while the player can skip to the next track
+    pub(crate) next: Option<KeyId>,
+    /// Lit while the player can go back to the previous track
+    pub(crate) previous: Option<KeyId>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StatusKey {
+    Shuffle,
+    LoopStatus,
+    Rate,
+    Next,
+    Previous,
+}
+
+impl MediaStatusKeys {
+    /// The LED of every status key that is set and in the keymap
+    fn leds(&self, key_led_map: &HashMap<KeyId, u32>) -> Vec<(StatusKey, u32)> {
+        [
+            (StatusKey::Shuffle, self.shuffle),
+            (StatusKey::LoopStatus, self.loop_status),
+            (StatusKey::Rate, self.rate),
+            (StatusKey::Next, self.next),
+            (StatusKey::Previous, self.previous),
+        ]
+        .into_iter()
+        .filter_map(|(status_key, key)| {
+            let key = key?;
+            let led = key_led_map.get(&key);
+            if led.is_none() {
+                eprintln!(
+                    "The {:?} status key {} isn't in the keymap",
+                    status_key, key
+                );
+            }
+            Some((status_key, *led?))
+        })
+        .collect()
+    }
+}
+
+/// Which players the module shows the progress of
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) enum PlayerSelection {
+    /// The playing player that matches the earliest color rule, or the first playing one if no
+    /// rule matches any of them
+    #[default]
+    FirstRuleMatch,
+    /// The playing player that most recently started playing or changed track
+    MostRecent,
+    /// Only the player with this bus name. The "org.mpris.MediaPlayer2." prefix can be left out
+    Pinned(String),
+    /// The LEDs are split into one segment with its own progress bar per player that isn't
+    /// stopped
+    Split,
+}
+
+impl PlayerSelection {
+    pub(crate) fn names() -> [&'static str; 4] {
+        ["FirstRuleMatch", "MostRecent", "Pinned", "Split"]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct MediaColorRule {
+    pub(crate) field: MediaField,
+    /// A regex that has to match somewhere in the field
+    pub(crate) pattern: String,
+    pub(crate) color: RGB8,
+}
+
+/// The part of a player or its current track that a rule is matched against
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum MediaField {
+    /// The MPRIS bus name, like "org.mpris.MediaPlayer2.spotify"
+    BusName,
+    Title,
+    Artist,
+    Album,
+    Url,
+}
+
+impl MediaField {
+    pub(crate) fn all() -> [MediaField; 5] {
+        [
+            MediaField::BusName,
+            MediaField::Title,
+            MediaField::Artist,
+            MediaField::Album,
+            MediaField::Url,
+        ]
+    }
+
+    fn value<'a>(&self, player: &'a PlayerState) -> Option<&'a str> {
+        match self {
+            MediaField::BusName => Some(&player.bus_name),
+            MediaField::Title => player.title.as_deref(),
+            MediaField::Artist => player.artist.as_deref(),
+            MediaField::Album => player.album.as_deref(),
+            MediaField::Url => player.url.as_deref(),
+        }
+    }
+}
+
+/// A color rule with its pattern compiled
+struct ColorRule {
+    field: MediaField,
+    regex: Regex,
+    color: RGB8,
+}
+
+impl ColorRule {
+    fn matches(&self, player: &PlayerState) -> bool {
+        self.field
+            .value(player)
+            .is_some_and(|value| self.regex.is_match(value))
+    }
+}
+
+/// Rules with invalid patterns are skipped
+fn compile_color_rules(color_rules: &[MediaColorRule]) -> Vec<ColorRule> {
+    color_rules
+        .iter()
+        .filter_map(|rule| match Regex::new(&rule.pattern) {
+            Ok(regex) => Some(ColorRule {
+                field: rule.field,
+                regex,
+                color: rule.color,
+            }),
+            Err(err) => {
+                eprintln!("Skipping media color rule with invalid pattern. {}", err);
+                None
+            }
+        })
+        .collect()
+}
+
+pub(crate) struct MediaModule {}
+
+impl MediaModule {
+    pub(crate) fn run(
+        task_tracker: &TaskTracker,
+        cancellation_token: CancellationToken,
+        mut sender: Sender<KeyboardControllerMessage>,
+        mut module_leds: Vec<Option<u32>>,
+        options: MediaModuleOptions,
+        key_led_map: HashMap<KeyId, u32>,
+    ) {
+        let color_rules = compile_color_rules(&options.color_rules);
+        let status_leds = options.status_keys.leds(&key_led_map);
+        module_leds.retain(|led| {
+            led.is_none_or(|led| !status_leds.iter().any(|&(_, status_led)| status_led == led))
+        });
+        task_tracker.spawn(async move {
+            let mut mpris = match Mpris::connect().await {
+                Ok(mpris) => mpris,
+                Err(err) => {
+                    eprintln!("Failed to connect to media players. {}", err);
+                    return;
+                }
+            };
+            // Colors of the album art that is shown, by URL, so each image is only loaded once.
+            // Art that is no longer shown is dropped
+            let mut album_art_colors: HashMap<String, Option<RGB8>> = HashMap::new();
+            // The color and progress of each segment when it was last drawn. Paused segments
+            // have no progress
+            let mut last_frame: Vec<(RGB8, Option<f32>)> = Vec::new();
+            let mut last_status_colors = Vec::new();
+            loop {
+                let segments = get_segments(mpris.players(), &color_rules, &options);
+                let shown_player = segments.iter().find_map(|&(_, player)| player);
+                let status_colors = status_colors(&status_leds, shown_player, &options);
+                for &(led, color) in &status_colors {
+                    if last_status_colors.contains(&(led, color)) {
+                        continue;
+                    }
+                    if let Err(err) = KeyboardController::update_led(&mut sender, led, color).await
+                    {
+                        eprintln!("{}", err)
+                    }
+                }
+                last_status_colors = status_colors;
+                let now = Instant::now();
+                let mut frame = Vec::new();
+                let mut shown_art_urls = Vec::new();
+                for (mut color, player) in segments {
+                    let art_url = player.and_then(|player| player.art_url.as_ref());
+                    if let (true, Some(art_url)) = (options.album_art_color, art_url) {
+                        if !album_art_colors.contains_key(art_url) {
+                            let album_art_color = album_art::album_art_color(art_url)
+                                .await
+                                .map_err(|err| eprintln!("{}", err))
+                                .ok();
+                            album_art_colors.insert(art_url.clone(), album_art_color);
+                        }
+                        if let Some(Some(album_art_color)) = album_art_colors.get(art_url) {
+                            color = *album_art_color;
+                        }
+                        shown_art_urls.push(art_url);
+                    }
+                    frame.push((color, player.map(|player| player.progress_at(now))));
+                }
+                album_art_colors.retain(|art_url, _| shown_art_urls.contains(&art_url));
+                let is_playing = frame.iter().any(|(_, progress)| progress.is_some());
+
+                for (i, &(color, progress)) in frame.iter().enumerate() {
+                    // Everything is redrawn when the segments change
+                    let last = (last_frame.len() == frame.len()).then(|| last_frame[i]);
+                    let render_output = Self::render_segment(
+                        &mut sender,
+                        segment_leds(&module_leds, i, frame.len()),
+                        color,
+                        progress,
+                        last,
+                        &options.progress_bar,
+                    )
+                    .await;
+                    if let Err(err) = render_output {
+                        eprintln!("{}", err)
+                    };
+                }
+                last_frame = frame;
+
+                // Players only signal changes, so the progress bar is moved along with a timer
+                let changed = tokio::select! {
+                    biased;
+                    _ = cancellation_token.cancelled() => {
+                        break;
+                    }
+                    change = mpris.wait_for_change() => {
+                        if let Err(err) = change {
+                            eprintln!("{}", err);
+                            break;
+                        }
+                        true
+                    }
+                    _ = tokio::time::sleep(Duration::from_millis(100)), if is_playing => false,
+                };
+                if changed {
+                    if let Err(err) = mpris.refresh().await {
+                        eprintln!("Failed to get the state of the media players. {}", err)
+                    }
+                }
+            }
+        });
+    }
+}
+
+impl MediaModule {
+    /// Only draws what changed since `last`, the color and progress the segment was last drawn
+    /// with
+    async fn render_segment(
+        sender: &mut Sender<KeyboardControllerMessage>,
+        leds: &[Option<u32>],
+        color: RGB8,
+        progress: Option<f32>,
+        last: Option<(RGB8, Option<f32>)>,
+        progress_bar: &ProgressBar,
+    ) -> anyhow::Result<()> {
+        match (progress, last) {
+            (None, Some((last_color, None))) if last_color == color => Ok(()),
+            (None, _) => Self::render_paused(sender, leds, color).await,
+            (Some(progress), last) => {
+                // The whole bar has to be redrawn when the color changes, not only the LEDs
+                // that moved
+                let last_progress = match last {
+                    Some((last_color, last_progress)) if last_color == color => last_progress,
+                    _ => None,
+                };
+                Self::render_progress(sender, leds, color, progress, last_progress, progress_bar)
+                    .await
+            }
+        }
+    }
+
+    async fn render_paused(
+        sender: &mut Sender<KeyboardControllerMessage>,
+        module_leds: &[Option<u32>],
+        color: RGB8,
+    ) -> anyhow::Result<()> {
+        // flatten() filters out None
+        for &led_index in module_leds.iter().flatten() {
+            KeyboardController::update_led_urgent(sender, led_index, color).await?;
+        }
+        Ok(())
+    }
+
+    async fn render_progress(
+        sender: &mut Sender<KeyboardControllerMessage>,
+        module_leds: &[Option<u32>],
+        color: RGB8,
+        progress: f32,
+        last_progress: Option<f32>,
+        progress_bar: &ProgressBar,
+    ) -> anyhow::Result<()> {
+        for (led_index, brightness) in
+            progress_bar.render_diff(module_leds, progress, last_progress)
+        {
+            KeyboardController::update_led(
+                sender,
+                led_index,
+                color.map(|comp| (comp as f32 * brightness) as u8),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// The color of each status key for the player whose progress is shown. They are all off when
+/// no player is playing
+fn status_colors(
+    status_leds: &[(StatusKey, u32)],
+    player: Option<&PlayerState>,
+    options: &MediaModuleOptions,
+) -> Vec<(u32, Color)> {
+    let off = Color::new(0, 0, 0);
+    status_leds
+        .iter()
+        .map(|&(status_key, led)| {
+            let Some(player) = player else {
+                return (led, off);
+            };
+            let lit = |on: bool| if on { options.status_color } else { off };
+            let color = match status_key {
+                StatusKey::Shuffle => lit(player.shuffle),
+                StatusKey::LoopStatus => match player.loop_status {
+                    LoopStatus::None => off,
+                    LoopStatus::Track => options.loop_track_color,
+                    LoopStatus::Playlist => options.status_color,
+                },
+                StatusKey::Rate => lit(player.rate != 1.),
+                StatusKey::Next => lit(player.can_go_next),
+                StatusKey::Previous => lit(player.can_go_previous),
+            };
+            (led, color)
+        })
+        .collect()
+}
+
+/// The LEDs of segment `index` when the module is split into `num_segments` segments
+fn segment_leds(module_leds: &[Option<u32>], index: usize, num_segments: usize) -> &[Option<u32>] {
+    let start = index * module_leds.len() / num_segments;
+    let end = (index + 1) * module_leds.len() / num_segments;
+    &module_leds[start..end]
+}
+
+fn player_color(
+    player: &PlayerState,
+    color_rules: &[ColorRule],
+    options: &MediaModuleOptions,
+) -> RGB8 {
+    color_rules
+        .iter()
+        .find(|rule| rule.matches(player))
+        .map_or(options.default_color, |rule| rule.color)
+}
+
+/// The color and player of each segment of the module. There is always at least one segment.
+/// Segments that show a paused player, or no player at all, don't have one
+fn get_segments<'a>(
+    players: &'a [PlayerState],
+    color_rules: &[ColorRule],
+    options: &MediaModuleOptions,
+) -> Vec<(RGB8, Option<&'a PlayerState>)> {
+    let playing_players = players
+        .iter()
+        .filter(|player| player.status == PlaybackStatus::Playing)
+        .collect::<Vec<&PlayerState>>();
+
+    let shown_player = match &options.player_selection {
+        PlayerSelection::FirstRuleMatch => color_rules
+            .iter()
+            .find_map(|rule| {
+                playing_players
+                    .iter()
+                    .find(|player| rule.matches(player))
+                    .copied()
+            })
+            .or(playing_players.first().copied()),
+        PlayerSelection::MostRecent => playing_players
+            .iter()
+            .max_by_key(|player| player.last_active)
+            .copied(),
+        PlayerSelection::Pinned(bus_name) => playing_players
+            .iter()
+            .find(|player| {
+                player.bus_name == *bus_name
+                    || player.bus_name.strip_prefix(PLAYER_BUS_NAME_PREFIX) == Some(bus_name)
+            })
+            .copied(),
+        PlayerSelection::Split => {
+            let segments = players
+                .iter()
+                .filter(|player| player.status != PlaybackStatus::Stopped)
+                .map(|player| match player.status {
+                    PlaybackStatus::Playing => {
+                        (player_color(player, color_rules, options), Some(player))
+                    }
+                    _ => (options.paused_color, None),
+                })
+                .collect::<Vec<(RGB8, Option<&PlayerState>)>>();
+            if !segments.is_empty() {
+                return segments;
+            }
+            None
+        }
+    };
+
+    vec![match shown_player {
+        Some(player) => (player_color(player, color_rules, options), Some(player)),
+        None => (options.paused_color, None),
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Instant;
+
+    use zbus::zvariant::{OwnedValue, Value};
+
+    use super::mpris::PlayerState;
+    use super::*;
+
+    fn player(bus_name: &str, status: &str, title: &str) -> PlayerState {
+        let metadata = HashMap::from([("xesam:title", Value::from(title))]);
+        let properties: HashMap<String, OwnedValue> = HashMap::from([
+            ("PlaybackStatus".to_owned(), Value::from(status).into()),
+            ("Metadata".to_owned(), Value::from(metadata).into()),
+        ]);
+        PlayerState::from_properties(bus_name, &properties, Instant::now())
+    }
+
+    #[test]
+    fn test_color_rules() {
+        let options = MediaModuleOptions::default();
+        let color_rules = compile_color_rules(&options.color_rules);
+        let firefox = player("org.mpris.MediaPlayer2.firefox", "Playing", "Netflix");
+        let spotify = player("org.mpris.MediaPlayer2.spotify", "Playing", "Song");
+        let paused_spotify = player("org.mpris.MediaPlayer2.spotify", "Paused", "Song");
+        let mpv = player("org.mpris.MediaPlayer2.mpv", "Playing", "Video");
+
+        // The first rule wins, even if a later one matches a player that comes first
+        let players = [firefox.clone(), spotify.clone()];
+        assert_eq!(
+            get_segments(&players, &color_rules, &options),
+            vec![(constants::SPOTIFY_MEDIA_PLAYING_COLOR, Some(&spotify))]
+        );
+
+        let players = [paused_spotify.clone(), firefox.clone()];
+        assert_eq!(
+            get_segments(&players, &color_rules, &options),
+            vec![(constants::NETFLIX_MEDIA_PLAYING_COLOR, Some(&firefox))]
+        );
+
+        let players = [paused_spotify.clone(), mpv.clone()];
+        assert_eq!(
+            get_segments(&players, &color_rules, &options),
+            vec![(options.default_color, Some(&mpv))]
+        );
+
+        let players = [paused_spotify];
+        assert_eq!(
+            get_segments(&players, &color_rules, &options),
+            vec![(options.paused_color, None)]
+        );
+    }
+
+    #[test]
+    fn test_player_selection() {
+        let mut options = MediaModuleOptions::default();
+        let color_rules = compile_color_rules(&options.color_rules);
+        let spotify = player("org.mpris.MediaPlayer2.spotify", "Playing", "Song");
+        let mut mpv = player("org.mpris.MediaPlayer2.mpv", "Playing", "Video");
+        mpv.last_active += Duration::from_secs(1);
+        let paused_firefox = player("org.mpris.MediaPlayer2.firefox", "Paused", "Netflix");
+        let stopped_vlc = player("org.mpris.MediaPlayer2.vlc", "Stopped", "");
+        let players = [
+            spotify.clone(),
+            mpv.clone(),
+            paused_firefox.clone(),
+            stopped_vlc,
+        ];
+
+        options.player_selection = PlayerSelection::MostRecent;
+        assert_eq!(
+            get_segments(&players, &color_rules, &options),
+            vec![(options.default_color, Some(&mpv))]
+        );
+
+        options.player_selection = PlayerSelection::Pinned("spotify".to_owned());
+        assert_eq!(
+            get_segments(&players, &color_rules, &options),
+            vec![(constants::SPOTIFY_MEDIA_PLAYING_COLOR, Some(&spotify))]
+        );
+        options.player_selection =
+            PlayerSelection::Pinned("org.mpris.MediaPlayer2.firefox".to_owned());
+        assert_eq!(
+            get_segments(&players, &color_rules, &options),
+            vec![(options.paused_color, None)]
+        );
+
+        options.player_selection = PlayerSelection::Split;
+        assert_eq!(
+            get_segments(&players, &color_rules, &options),
+            vec![
+                (constants::SPOTIFY_MEDIA_PLAYING_COLOR, Some(&spotify)),
+                (options.default_color, Some(&mpv)),
+                (options.paused_color, None),
+            ]
+        );
+        assert_eq!(
+            get_segments(&[], &color_rules, &options),
+            vec![(options.paused_color, None)]
+        );
+    }
+
+    #[test]
+    fn test_status_colors() {
+        let options = MediaModuleOptions::default();
+        let metadata = HashMap::from([("xesam:title", Value::from("Song"))]);
+        let properties: HashMap<String, OwnedValue> = HashMap::from([
+            ("PlaybackStatus".to_owned(), Value::from("Playing").into()),
+            ("Metadata".to_owned(), Value::from(metadata).into()),
+            ("Shuffle".to_owned(), Value::from(false).into()),
+            ("LoopStatus".to_owned(), Value::from("Track").into()),
+            ("Rate".to_owned(), Value::from(1.5).into()),
+            ("CanGoNext".to_owned(), Value::from(true).into()),
+            ("CanGoPrevious".to_owned(), Value::from(false).into()),
+        ]);
+        let player =
+            PlayerState::from_properties("org.mpris.MediaPlayer2.mpv", &properties, Instant::now());
+        let status_keys = MediaStatusKeys {
+            shuffle: Some(KeyId::Char('s')),
+            loop_status: Some(KeyId::Char('l')),
+            rate: Some(KeyId::Char('r')),
+            next: Some(KeyId::Char('n')),
+            previous: Some(KeyId::Char('p')),
+        };
+        let key_led_map = HashMap::from([
+            (KeyId::Char('s'), 0),
+            (KeyId::Char('l'), 1),
+            (KeyId::Char('r'), 2),
+            (KeyId::Char('n'), 3),
+        ]);
+        // The previous key isn't in the keymap
+        let status_leds = status_keys.leds(&key_led_map);
+        let off = Color::new(0, 0, 0);
+        assert_eq!(
+            status_colors(&status_leds, Some(&player), &options),
+            vec![
+                (0, off),
+                (1, options.loop_track_color),
+                (2, options.status_color),
+                (3, options.status_color),
+            ]
+        );
+        assert_eq!(
+            status_colors(&status_leds, None, &options),
+            vec![(0, off), (1, off), (2, off), (3, off)]
+        );
+    }
+
+    #[test]
+    fn test_segment_leds() {
+        let module_leds = [Some(0), Some(1), Some(2), Some(3), Some(4)];
+        assert_eq!(segment_leds(&module_leds, 0, 2), &module_leds[..2]);
+        assert_eq!(segment_leds(&module_leds, 1, 2), &module_leds[2..]);
+        assert_eq!(segment_leds(&module_leds, 0, 1), &module_leds[..]);
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_skipped() {
+        let color_rules = compile_color_rules(&[MediaColorRule {
+            field: MediaField::Title,
+            pattern: "(".to_owned(),
+            color: RGB8::new(1, 2, 3),
+        }]);
+        assert!(color_rules.is_empty());
+    }
+}
@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use futures_util::stream::{self, SelectAll, StreamExt};
+use zbus::fdo::DBusProxy;
+use zbus::zvariant::{OwnedValue, Value};
+use zbus::{Connection, MatchRule, MessageStream, MessageType};
+
+pub(crate) const PLAYER_BUS_NAME_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const PLAYER_PATH: &str = "/org/mpris/MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PlaybackStatus {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum LoopStatus {
+    None,
+    Track,
+    Playlist,
+}
+
+/// The state of an MPRIS player when it was last queried
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PlayerState {
+    /// The well-known bus name, like "org.mpris.MediaPlayer2.spotify"
+    pub(crate) bus_name: String,
+    pub(crate) status: PlaybackStatus,
+    pub(crate) title: Option<String>,
+    /// All artists of the track, separated by ", "
+    pub(crate) artist: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) url: Option<String>,
+    pub(crate) art_url: Option<String>,
+    pub(crate) length: Option<Duration>,
+    pub(crate) shuffle: bool,
+    pub(crate) loop_status: LoopStatus,
+    pub(crate) can_go_next: bool,
+    pub(crate) can_go_previous: bool,
+    position: Duration,
+    /// How fast the track plays. 1.0 is normal speed
+    pub(crate) rate: f64,
+    /// When `position` was queried
+    queried_at: Instant,
+    /// When the player last started playing or changed track
+    pub(crate) last_active: Instant,
+}
+
+impl PlayerState {
+    /// Reads the state from the properties of the `org.mpris.MediaPlayer2.Player` interface
+    pub(crate) fn from_properties(
+        bus_name: &str,
+        properties: &HashMap<String, OwnedValue>,
+        queried_at: Instant,
+    ) -> Self {
+        let status = match properties
+            .get("PlaybackStatus")
+            .and_then(|value| as_str(value))
+        {
+            Some("Playing") => PlaybackStatus::Playing,
+            Some("Paused") => PlaybackStatus::Paused,
+            _ => PlaybackStatus::Stopped,
+        };
+        let loop_status = match properties.get("LoopStatus").and_then(|value| as_str(value)) {
+            Some("Track") => LoopStatus::Track,
+            Some("Playlist") => LoopStatus::Playlist,
+            _ => LoopStatus::None,
+        };
+        let property_bool = |key: &str| {
+            matches!(
+                properties.get(key).map(|value| unwrap_variant(value)),
+                Some(Value::Bool(true))
+            )
+        };
+        let metadata = properties
+            .get("Metadata")
+            .and_then(|metadata| HashMap::<String, OwnedValue>::try_from(metadata.clone()).ok())
+            .unwrap_or_default();
+        let metadata_str = |key: &str| {
+            metadata
+                .get(key)
+                .and_then(|value| as_str(value))
+                .map(str::to_owned)
+        };
+        let artist = metadata.get("xesam:artist").and_then(|artists| {
+            match unwrap_variant(artists) {
+                Value::Array(artists) => Some(
+                    artists
+                        .get()
+                        .iter()
+                        .filter_map(as_str)
+                        .collect::<Vec<&str>>()
+                        .join(", "),
+                ),
+                // Some players send a single string, even though the spec says otherwise
+                artist => as_str(artist).map(str::to_owned),
+            }
+        });
+
+        Self {
+            bus_name: bus_name.to_owned(),
+            status,
+            title: metadata_str("xesam:title"),
+            artist,
+            album: metadata_str("xesam:album"),
+            url: metadata_str("xesam:url"),
+            art_url: metadata_str("mpris:artUrl"),
+            length: metadata
+                .get("mpris:length")
+                .and_then(|length| as_micros(length)),
+            shuffle: property_bool("Shuffle"),
+            loop_status,
+            can_go_next: property_bool("CanGoNext"),
+            can_go_previous: property_bool("CanGoPrevious"),
+            position: properties
+                .get("Position")
+                .and_then(|position| as_micros(position))
+                .unwrap_or_default(),
+            rate: match properties.get("Rate").map(|rate| unwrap_variant(rate)) {
+                Some(Value::F64(rate)) => *rate,
+                _ => 1.,
+            },
+            queried_at,
+            last_active: queried_at,
+        }
+    }
+
+    /// Players don't signal position changes during playback, so it is interpolated from the
+    /// last queried position
+    pub(crate) fn position_at(&self, now: Instant) -> Duration {
+        if self.status != PlaybackStatus::Playing {
+            return self.position;
+        }
+        let elapsed = now.saturating_duration_since(self.queried_at);
+        self.position + elapsed.mul_f64(self.rate.max(0.))
+    }
+
+    /// How far into the track the player is, from 0.0 to 1.0. Tracks without a length count as
+    /// finished
+    pub(crate) fn progress_at(&self, now: Instant) -> f32 {
+        match self.length {
+            Some(length) if !length.is_zero() => {
+                (self.position_at(now).as_secs_f64() / length.as_secs_f64()).min(1.) as f32
+            }
+            _ => 1.,
+        }
+    }
+}
+
+fn unwrap_variant<'a>(value: &'a Value<'a>) -> &'a Value<'a> {
+    match value {
+        Value::Value(inner) => unwrap_variant(inner),
+        value => value,
+    }
+}
+
+fn as_str<'a>(value: &'a Value<'a>) -> Option<&'a str> {
+    match unwrap_variant(value) {
+        Value::Str(string) => Some(string.as_str()),
+        Value::ObjectPath(path) => Some(path.as_str()),
+        _ => None,
+    }
+}
+
+/// Lengths and positions are in microseconds. Some players send them unsigned
+fn as_micros(value: &Value) -> Option<Duration> {
+    match unwrap_variant(value) {
+        Value::I64(micros) => Some(Duration::from_micros((*micros).max(0) as u64)),
+        Value::U64(micros) => Some(Duration::from_micros(*micros)),
+        _ => None,
+    }
+}
+
+/// A client for all MPRIS players on the session bus
+pub(crate) struct Mpris {
+    connection: Connection,
+    signals: SelectAll<MessageStream>,
+    players: Vec<PlayerState>,
+}
+
+impl Mpris {
+    pub(crate) async fn connect() -> anyhow::Result<Self> {
+        let connection = Connection::session()
+            .await
+            .context("Failed to connect to the D-Bus session bus")?;
+        let rules = [
+            // Playback status and metadata changes
+            MatchRule::builder()
+                .msg_type(MessageType::Signal)
+                .interface("org.freedesktop.DBus.Properties")?
+                .member("PropertiesChanged")?
+                .path(PLAYER_PATH)?
+                .arg(0, PLAYER_INTERFACE)?
+                .build(),
+            MatchRule::builder()
+                .msg_type(MessageType::Signal)
+                .interface(PLAYER_INTERFACE)?
+                .member("Seeked")?
+                .path(PLAYER_PATH)?
+                .build(),
+            // Players appearing and disappearing
+            MatchRule::builder()
+                .msg_type(MessageType::Signal)
+                .sender("org.freedesktop.DBus")?
+                .interface("org.freedesktop.DBus")?
+                .member("NameOwnerChanged")?
+                .arg0ns("org.mpris.MediaPlayer2")?
+                .build(),
+        ];
+        let mut signals = Vec::new();
+        for rule in rules {
+            signals.push(MessageStream::for_match_rule(rule, &connection, None).await?);
+        }
+        let mut mpris = Self {
+            connection,
+            signals: stream::select_all(signals),
+            players: Vec::new(),
+        };
+        mpris.refresh().await?;
+        Ok(mpris)
+    }
+
+    /// All players, sorted by bus name
+    pub(crate) fn players(&self) -> &[PlayerState] {
+        &self.players
+    }
+
+    /// Waits for a signal from any player. Cancel safe
+    pub(crate) async fn wait_for_change(&mut self) -> anyhow::Result<()> {
+        match self.signals.next().await {
+            Some(Ok(_)) => Ok(()),
+            Some(Err(err)) => Err(err.into()),
+            None => bail!("The D-Bus connection was closed"),
+        }
+    }
+
+    /// Queries the state of all players again
+    pub(crate) async fn refresh(&mut self) -> anyhow::Result<()> {
+        let mut bus_names = DBusProxy::new(&self.connection)
+            .await?
+            .list_names()
+            .await?
+            .into_iter()
+            .map(|bus_name| bus_name.to_string())
+            .filter(|bus_name| bus_name.starts_with(PLAYER_BUS_NAME_PREFIX))
+            .collect::<Vec<String>>();
+        bus_names.sort();
+
+        let mut players = Vec::new();
+        for bus_name in bus_names {
+            // Players can quit at any time, so failing to query one isn't an error
+            let Ok(properties) = self.get_player_properties(&bus_name).await else {
+                continue;
+            };
+            let mut player = PlayerState::from_properties(&bus_name, &properties, Instant::now());
+            if let Some(previous) = self
+                .players
+                .iter()
+                .find(|previous| previous.bus_name == bus_name)
+            {
+                if previous.status == player.status && previous.title == player.title {
+                    player.last_active = previous.last_active;
+                }
+            }
+            players.push(player);
+        }
+        self.players = players;
+        Ok(())
+    }
+
+    async fn get_player_properties(
+        &self,
+        bus_name: &str,
+    ) -> anyhow::Result<HashMap<String, OwnedValue>> {
+        let reply = self
+            .connection
+            .call_method(
+                Some(bus_name),
+                PLAYER_PATH,
+                Some("org.freedesktop.DBus.Properties"),
+                "GetAll",
+                &(PLAYER_INTERFACE),
+            )
+            .await?;
+        Ok(reply.body()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+
+    use zbus::zvariant::{OwnedValue, Value};
+
+    use super::{LoopStatus, PlaybackStatus, PlayerState};
+
+    fn properties(status: &str, length: i64, position: i64) -> HashMap<String, OwnedValue> {
+        let metadata = HashMap::from([
+            ("xesam:title", Value::from("Song")),
+            ("xesam:artist", Value::from(vec!["A", "B"])),
+            ("mpris:length", Value::from(length)),
+        ]);
+        HashMap::from([
+            ("PlaybackStatus".to_owned(), Value::from(status).into()),
+            ("Metadata".to_owned(), Value::from(metadata).into()),
+            ("Position".to_owned(), Value::from(position).into()),
+            ("Rate".to_owned(), Value::from(2.).into()),
+            ("Shuffle".to_owned(), Value::from(true).into()),
+            ("LoopStatus".to_owned(), Value::from("Playlist").into()),
+            ("CanGoNext".to_owned(), Value::from(true).into()),
+        ])
+    }
+
+    #[test]
+    fn test_from_properties() {
+        let player = PlayerState::from_properties(
+            "org.mpris.MediaPlayer2.spotify",
+            &properties("Playing", 10_000_000, 0),
+            Instant::now(),
+        );
+        assert_eq!(player.status, PlaybackStatus::Playing);
+        assert_eq!(player.title.as_deref(), Some("Song"));
+        assert_eq!(player.artist.as_deref(), Some("A, B"));
+        assert_eq!(player.album, None);
+        assert_eq!(player.length, Some(Duration::from_secs(10)));
+        assert!(player.shuffle);
+        assert_eq!(player.loop_status, LoopStatus::Playlist);
+        assert!(player.can_go_next);
+        assert!(!player.can_go_previous);
+    }
+
+    #[test]
+    fn test_position_interpolation() {
+        let queried_at = Instant::now();
+        let later = queried_at + Duration::from_secs(1);
+        let playing = PlayerState::from_properties(
+            "org.mpris.MediaPlayer2.mpv",
+            &properties("Playing", 10_000_000, 1_000_000),
+            queried_at,
+        );
+        // The rate is 2, so one second in, the position has moved two seconds
+        assert_eq!(playing.position_at(later), Duration::from_secs(3));
+        assert_eq!(playing.progress_at(later), 0.3);
+
+        let paused = PlayerState::from_properties(
+            "org.mpris.MediaPlayer2.mpv",
+            &properties("Paused", 10_000_000, 1_000_000),
+            queried_at,
+        );
+        assert_eq!(paused.position_at(later), Duration::from_secs(1));
+
+        let no_length = PlayerState::from_properties(
+            "org.mpris.MediaPlayer2.mpv",
+            &properties("Playing", 0, 1_000_000),
+            queried_at,
+        );
+        assert_eq!(no_length.progress_at(later), 1.);
+    }
+}
@@ -0,0 +1,148 @@
+use rgb::RGB8;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+use crate::core::audio::{AudioDevice, AudioSource, PactlAudio};
+use crate::core::constants;
+use crate::core::keyboard_controller::{KeyboardController, KeyboardControllerMessage};
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub(crate) struct MicMuteModuleOptions {
+    pub(crate) muted_color: RGB8,
+    /// Black turns the keys off while the microphone is live
+    pub(crate) live_color: RGB8,
+}
+
+impl Default for MicMuteModuleOptions {
+    fn default() -> Self {
+        Self {
+            muted_color: constants::DEFAULT_MIC_MUTED_COLOR,
+            live_color: constants::DEFAULT_MIC_LIVE_COLOR,
+        }
+    }
+}
+
+pub(crate) struct MicMuteModule {}
+
+impl MicMuteModule {
+    pub fn run(
+        task_tracker: &TaskTracker,
+        cancellation_token: CancellationToken,
+        sender: Sender<KeyboardControllerMessage>,
+        module_leds: Vec<Option<u32>>,
+        options: MicMuteModuleOptions,
+    ) {
+        task_tracker.spawn(async move {
+            let mut pactl = match PactlAudio::new(AudioDevice::Source) {
+                Ok(pactl) => pactl,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return;
+                }
+            };
+            Self::run_with_source(
+                &mut pactl,
+                cancellation_token,
+                sender,
+                &module_leds,
+                &options,
+            )
+            .await
+        });
+    }
+
+    async fn run_with_source(
+        source: &mut impl AudioSource,
+        cancellation_token: CancellationToken,
+        mut sender: Sender<KeyboardControllerMessage>,
+        module_leds: &[Option<u32>],
+        options: &MicMuteModuleOptions,
+    ) {
+        let mut last_muted = None;
+        loop {
+            match source.get_state().await {
+                Ok(state) if last_muted != Some(state.muted) => {
+                    last_muted = Some(state.muted);
+                    let color = if state.muted {
+                        options.muted_color
+                    } else {
+                        options.live_color
+                    };
+                    // Being live when you think you're muted is the worst case, so this can't
+                    // wait for other modules
+                    for &led in module_leds.iter().flatten() {
+                        if let Err(err) =
+                            KeyboardController::update_led_urgent(&mut sender, led, color).await
+                        {
+                            eprintln!("{}", err)
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => eprintln!("Failed to get the microphone state. {}", err),
+            }
+
+            tokio::select! {
+                biased;
+                _ = cancellation_token.cancelled() => {
+                    break;
+                }
+                change = source.wait_for_change() => {
+                    if let Err(err) = change {
+                        eprintln!("{}", err);
+                        break;
+                    }
+                }
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::audio::AudioState;
+    use crate::core::scripted::{run_until, Scripted};
+
+    #[tokio::test]
+    async fn test_follows_mute_state() {
+        let state = |muted| AudioState { volume: 1., muted };
+        // The volume change in the middle doesn't redraw anything
+        let mut source = Scripted::new(vec![
+            state(false),
+            AudioState {
+                volume: 0.5,
+                muted: false,
+            },
+            state(true),
+        ]);
+        let options = MicMuteModuleOptions::default();
+        let messages = run_until(
+            |cancellation_token, sender| {
+                MicMuteModule::run_with_source(
+                    &mut source,
+                    cancellation_token,
+                    sender,
+                    &[Some(4), None, Some(5)],
+                    &options,
+                )
+            },
+            |messages| messages.len() == 4,
+        )
+        .await;
+        assert_eq!(
+            messages
+                .iter()
+                .map(|message| (message.led(), message.color()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some(4), options.live_color),
+                (Some(5), options.live_color),
+                (Some(4), options.muted_color),
+                (Some(5), options.muted_color),
+            ]
+        );
+    }
+}
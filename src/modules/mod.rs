@@ -1,4 +1,9 @@
+pub(crate) mod binding_mode;
+pub(crate) mod keyboard_layout;
 pub(crate) mod media_playing;
+pub(crate) mod mic_mute;
 pub(crate) mod noise;
+pub(crate) mod shortcut_hints;
 pub(crate) mod starfield;
+pub(crate) mod volume;
 pub(crate) mod workspaces;
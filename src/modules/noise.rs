@@ -1,7 +1,7 @@
 use std::time::{Duration, Instant};
 
 use hsv::hsv_to_rgb;
-use noise::NoiseFn;
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin, SuperSimplex, Worley};
 use rand::random;
 use rgb::{RGB, RGB8};
 use serde::{Deserialize, Serialize};
@@ -10,26 +10,152 @@ use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 
 use crate::core::keyboard_controller::{KeyboardController, KeyboardControllerMessage};
+use crate::core::keymap::LedPosition;
 use crate::core::utils;
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) enum NoiseFunction {
+    #[default]
+    SuperSimplex,
+    Perlin,
+    /// Cells, like cracked mud or stained glass
+    Worley,
+    /// Several octaves of SuperSimplex layered on top of each other, for more detail
+    Fbm,
+}
+
+impl NoiseFunction {
+    pub(crate) fn all() -> [NoiseFunction; 4] {
+        [
+            NoiseFunction::SuperSimplex,
+            NoiseFunction::Perlin,
+            NoiseFunction::Worley,
+            NoiseFunction::Fbm,
+        ]
+    }
+
+    fn generator(&self, seed: u32, octaves: usize) -> Box<dyn NoiseFn<f64, 3>> {
+        match self.sendable_generator(seed, octaves) {
+            Some(noise) => noise,
+            None => Box::new(Worley::new(seed)),
+        }
+    }
+
+    /// A generator that can be kept across the awaits of the module's loop. Worley noise can't
+    /// be sent between threads, so there is none for it
+    fn sendable_generator(
+        &self,
+        seed: u32,
+        octaves: usize,
+    ) -> Option<Box<dyn NoiseFn<f64, 3> + Send>> {
+        match self {
+            NoiseFunction::SuperSimplex => Some(Box::new(SuperSimplex::new(seed))),
+            NoiseFunction::Perlin => Some(Box::new(Perlin::new(seed))),
+            NoiseFunction::Worley => None,
+            NoiseFunction::Fbm => Some(Box::new(
+                Fbm::<SuperSimplex>::new(seed).set_octaves(octaves),
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "NoiseModuleConfig")]
 pub(crate) struct NoiseModuleOptions {
-    pub(crate) color1: RGB8,
-    pub(crate) color2: RGB8,
-    /// Per second
+    pub(crate) function: NoiseFunction,
+    /// Only used by `NoiseFunction::Fbm`
+    pub(crate) octaves: usize,
+    /// A random seed is picked on every start if this isn't set
+    pub(crate) seed: Option<u32>,
+    /// Colors spread evenly from the lowest to the highest value of the noise
+    pub(crate) gradient: Vec<RGB8>,
+    /// How far the noise moves per second. It used to move this far once for every LED on every
+    /// frame instead, so 0.5 now looks about like 0.01 did with a keyboard's worth of LEDs
     pub(crate) speed: f32,
     /// Divides coordinates by value
-    #[serde(default)]
     pub(crate) zoom_factor: f32,
 }
 
 impl Default for NoiseModuleOptions {
     fn default() -> Self {
         Self {
-            color1: RGB::from(hsv_to_rgb(44., 0.99, 0.02)),
-            color2: RGB::from(hsv_to_rgb(44., 0.99, 0.15)),
-            speed: 0.01,
-            zoom_factor: 3.,
+            function: NoiseFunction::default(),
+            octaves: default_octaves(),
+            seed: None,
+            gradient: default_gradient(),
+            speed: default_speed(),
+            zoom_factor: default_zoom_factor(),
+        }
+    }
+}
+
+fn default_octaves() -> usize {
+    4
+}
+
+fn default_gradient() -> Vec<RGB8> {
+    vec![
+        RGB::from(hsv_to_rgb(44., 0.99, 0.02)),
+        RGB::from(hsv_to_rgb(44., 0.99, 0.15)),
+    ]
+}
+
+fn default_speed() -> f32 {
+    0.5
+}
+
+/// What speeds from before it was per second are multiplied by to look about as fast as they did
+const OLD_SPEED_SCALE: f32 = 50.;
+
+fn default_zoom_factor() -> f32 {
+    3.
+}
+
+/// How the options are read. Configs from before the gradient have `color1` and `color2` instead,
+/// and their speed is from before it was per second
+#[derive(Deserialize)]
+struct NoiseModuleConfig {
+    #[serde(default)]
+    function: NoiseFunction,
+    #[serde(default = "default_octaves")]
+    octaves: usize,
+    #[serde(default)]
+    seed: Option<u32>,
+    #[serde(default)]
+    gradient: Vec<RGB8>,
+    color1: Option<RGB8>,
+    color2: Option<RGB8>,
+    #[serde(default = "default_speed")]
+    speed: f32,
+    #[serde(default = "default_zoom_factor")]
+    zoom_factor: f32,
+}
+
+impl From<NoiseModuleConfig> for NoiseModuleOptions {
+    fn from(config: NoiseModuleConfig) -> Self {
+        let is_old = config.color1.is_some() || config.color2.is_some();
+        let gradient = if !config.gradient.is_empty() {
+            config.gradient
+        } else if is_old {
+            let default = default_gradient();
+            vec![
+                config.color1.unwrap_or(default[0]),
+                config.color2.unwrap_or(default[1]),
+            ]
+        } else {
+            default_gradient()
+        };
+        Self {
+            function: config.function,
+            octaves: config.octaves,
+            seed: config.seed,
+            gradient,
+            speed: if is_old {
+                config.speed * OLD_SPEED_SCALE
+            } else {
+                config.speed
+            },
+            zoom_factor: config.zoom_factor,
         }
     }
 }
@@ -42,14 +168,19 @@ impl NoiseModule {
         cancellation_token: CancellationToken,
         mut sender: Sender<KeyboardControllerMessage>,
         module_leds: Vec<Option<u32>>,
-        _options: NoiseModuleOptions,
+        options: NoiseModuleOptions,
+        positions: Vec<Option<LedPosition>>,
     ) {
+        let coordinates = led_coordinates(&module_leds, &positions);
         task_tracker.spawn(async move {
-            let options = NoiseModuleOptions::default();
             let mut depth = 0.;
             let mut last_update = Instant::now();
-            let noise = noise::SuperSimplex::new(0);
-            let offset: (f64, f64) = (random(), random());
+            // A fixed seed should give the same pattern every time, so it isn't moved around
+            let (seed, offset) = match options.seed {
+                Some(seed) => (seed, (0., 0.)),
+                None => (random(), (random(), random())),
+            };
+            let noise = options.function.sendable_generator(seed, options.octaves);
 
             loop {
                 if cancellation_token.is_cancelled() {
@@ -57,56 +188,159 @@ impl NoiseModule {
                 }
 
                 let now = Instant::now();
-                // If we have two nones back to back, we consider that a line break
-                let mut back_to_back_nones = 0;
-                let mut current_row = 0;
-                let mut first_in_this_row = 0;
-                for (i, led) in module_leds.iter().enumerate() {
-                    let Some(led) = led else {
-                        back_to_back_nones += 1;
-                        if back_to_back_nones == 2 {
-                            current_row += 1;
-                            first_in_this_row = i + 1;
-                            back_to_back_nones = 0;
-                        }
-                        continue;
-                    };
-
-                    let x = i - first_in_this_row;
-                    KeyboardController::update_led(
-                        &mut sender,
-                        *led,
-                        led_value(
-                            &noise,
-                            options.color1,
-                            options.color2,
-                            depth as f64,
-                            x as f64 / options.zoom_factor as f64 + offset.0,
-                            current_row as f64 / options.zoom_factor as f64 + offset.1,
-                        ),
-                    )
-                    .await
-                    .unwrap();
-
-                    depth += (now - last_update).as_secs_f32() * options.speed;
-                    back_to_back_nones = 0;
+                depth += (now - last_update).as_secs_f64() * options.speed as f64;
+                last_update = now;
+                let colors = match &noise {
+                    Some(noise) => led_colors(&**noise, &coordinates, &options, depth, offset),
+                    // Worley can't be kept across awaits, so one is made for every frame
+                    None => {
+                        let noise = options.function.generator(seed, options.octaves);
+                        led_colors(&*noise, &coordinates, &options, depth, offset)
+                    }
+                };
+                for (led, color) in colors {
+                    if let Err(err) = KeyboardController::update_led(&mut sender, led, color).await
+                    {
+                        eprintln!("{}", err)
+                    }
                 }
 
-                last_update = Instant::now();
                 tokio::time::sleep(Duration::from_millis(20)).await;
             }
         });
     }
 }
 
-fn led_value<T: NoiseFn<f64, 3>>(
-    noise: &T,
-    background: RGB8,
-    target: RGB8,
+/// Where each LED is, in keys. Positions from the keymap are used if it knows all of the LEDs,
+/// otherwise they are laid out in the order of `module_leds`, one row at a time
+fn led_coordinates(
+    module_leds: &[Option<u32>],
+    positions: &[Option<LedPosition>],
+) -> Vec<(u32, f64, f64)> {
+    let all_known = module_leds
+        .iter()
+        .zip(positions)
+        .all(|(led, position)| led.is_none() || position.is_some());
+    if all_known {
+        return module_leds
+            .iter()
+            .zip(positions)
+            .filter_map(|(led, position)| {
+                let (x, y) = position.as_ref()?.center();
+                Some(((*led)?, x as f64, y as f64))
+            })
+            .collect();
+    }
+    let mut out = Vec::new();
+    for (y, row) in utils::split_rows(module_leds).iter().enumerate() {
+        for (x, led) in row.iter().enumerate() {
+            if let Some(led) = led {
+                out.push((*led, x as f64, y as f64));
+            }
+        }
+    }
+    out
+}
+
+fn led_colors(
+    noise: &dyn NoiseFn<f64, 3>,
+    coordinates: &[(u32, f64, f64)],
+    options: &NoiseModuleOptions,
     depth: f64,
-    x: f64,
-    y: f64,
-) -> RGB<u8> {
-    let interpolation = noise.get([x, y, depth]);
-    utils::interpolate(background, target, interpolation as f32)
+    offset: (f64, f64),
+) -> Vec<(u32, RGB8)> {
+    let zoom_factor = options.zoom_factor as f64;
+    coordinates
+        .iter()
+        .map(|&(led, x, y)| {
+            let value = noise.get([
+                x / zoom_factor + offset.0,
+                y / zoom_factor + offset.1,
+                depth,
+            ]);
+            // The noise goes from about -1.0 to 1.0
+            (
+                led,
+                gradient_color(&options.gradient, (value as f32 + 1.) / 2.),
+            )
+        })
+        .collect()
+}
+
+/// The color at `position` (0.0 to 1.0) of a gradient with evenly spaced stops
+fn gradient_color(gradient: &[RGB8], position: f32) -> RGB8 {
+    match gradient {
+        [] => RGB8::new(0, 0, 0),
+        [color] => *color,
+        _ => {
+            let scaled = position.clamp(0., 1.) * (gradient.len() - 1) as f32;
+            let i = (scaled.floor() as usize).min(gradient.len() - 2);
+            utils::interpolate(gradient[i], gradient[i + 1], scaled - i as f32)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rgb::RGB8;
+
+    use super::{gradient_color, led_coordinates, NoiseFunction, NoiseModuleOptions};
+    use crate::core::keymap::LedPosition;
+
+    #[test]
+    fn test_gradient_color() {
+        let gradient = [
+            RGB8::new(0, 0, 0),
+            RGB8::new(200, 0, 0),
+            RGB8::new(200, 100, 0),
+        ];
+        assert_eq!(gradient_color(&gradient, 0.), RGB8::new(0, 0, 0));
+        assert_eq!(gradient_color(&gradient, 0.25), RGB8::new(100, 0, 0));
+        assert_eq!(gradient_color(&gradient, 0.75), RGB8::new(200, 50, 0));
+        assert_eq!(gradient_color(&gradient, 1.), RGB8::new(200, 100, 0));
+        assert_eq!(gradient_color(&gradient, 1.5), RGB8::new(200, 100, 0));
+        assert_eq!(gradient_color(&gradient[..1], 0.5), RGB8::new(0, 0, 0));
+    }
+
+    #[test]
+    fn test_old_config() {
+        let options: NoiseModuleOptions = serde_yaml::from_str(
+            "color1: {r: 1, g: 2, b: 3}\ncolor2: {r: 4, g: 5, b: 6}\nspeed: 0.01\n",
+        )
+        .unwrap();
+        assert_eq!(
+            options.gradient,
+            vec![RGB8::new(1, 2, 3), RGB8::new(4, 5, 6)]
+        );
+        assert_eq!(options.function, NoiseFunction::SuperSimplex);
+        assert_eq!(options.zoom_factor, 3.);
+        assert_eq!(options.seed, None);
+        // The old default speed, which was applied once per LED
+        assert_eq!(options.speed, 0.5);
+
+        let options: NoiseModuleOptions =
+            serde_yaml::from_str("gradient: [{r: 1, g: 2, b: 3}]\nspeed: 0.01\n").unwrap();
+        assert_eq!(options.speed, 0.01);
+    }
+
+    #[test]
+    fn test_led_coordinates() {
+        let module_leds = [Some(0), Some(1), None, None, Some(2)];
+        let space = LedPosition {
+            x: 3.,
+            y: 4.,
+            width: 6.,
+        };
+        let positions = [Some(space), Some(space), None, None, Some(space)];
+        assert_eq!(
+            led_coordinates(&module_leds, &positions),
+            vec![(0, 6., 4.5), (1, 6., 4.5), (2, 6., 4.5)]
+        );
+        // Falls back to the order of the LEDs if the keymap doesn't know one of them
+        let positions = [Some(space), None, None, None, Some(space)];
+        assert_eq!(
+            led_coordinates(&module_leds, &positions),
+            vec![(0, 0., 0.), (1, 1., 0.), (2, 0., 1.)]
+        );
+    }
 }
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use openrgb::data::Color;
+use rgb::RGB8;
+use serde::{Deserialize, Serialize};
+use swayipc_async::{Event, EventType};
+use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+use crate::core::constants;
+use crate::core::key_id::KeyId;
+use crate::core::keyboard_controller::{KeyboardController, KeyboardControllerMessage};
+use crate::core::sway_config::{self, SwayBinding};
+use crate::core::sway_ipc::{SwayIpc, SwayMessage};
+
+/// Sway doesn't tell clients when a modifier is pressed, except for bars in hide mode. This module
+/// therefore needs a bar with `mode hide` and `modifier` set to the same modifier as in the options
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ShortcutHintsModuleOptions {
+    /// Bindings with this modifier are shown, like "Mod4" or "Mod1"
+    pub(crate) modifier: String,
+    pub(crate) workspace_color: RGB8,
+    pub(crate) exec_color: RGB8,
+    pub(crate) focus_color: RGB8,
+    pub(crate) move_color: RGB8,
+    /// The color of bindings with any other command
+    pub(crate) other_color: RGB8,
+}
+
+impl Default for ShortcutHintsModuleOptions {
+    fn default() -> Self {
+        Self {
+            modifier: "Mod4".to_owned(),
+            workspace_color: constants::WORKSPACE_SHORTCUT_COLOR,
+            exec_color: constants::EXEC_SHORTCUT_COLOR,
+            focus_color: constants::FOCUS_SHORTCUT_COLOR,
+            move_color: constants::MOVE_SHORTCUT_COLOR,
+            other_color: constants::OTHER_SHORTCUT_COLOR,
+        }
+    }
+}
+
+impl ShortcutHintsModuleOptions {
+    /// The color of a binding, chosen by the category of its command
+    fn binding_color(&self, binding: &SwayBinding) -> RGB8 {
+        let mut words = binding.command.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("workspace"), _) | (Some("move"), Some("workspace")) => self.workspace_color,
+            (Some("exec"), _) => self.exec_color,
+            (Some("focus"), _) => self.focus_color,
+            (Some("move"), _) => self.move_color,
+            _ => self.other_color,
+        }
+    }
+}
+
+pub(crate) struct ShortcutHintsModule {}
+
+impl ShortcutHintsModule {
+    pub fn run(
+        task_tracker: &TaskTracker,
+        cancellation_token: CancellationToken,
+        mut sender: Sender<KeyboardControllerMessage>,
+        module_leds: Vec<Option<u32>>,
+        options: ShortcutHintsModuleOptions,
+        key_led_map: HashMap<KeyId, u32>,
+    ) {
+        task_tracker.spawn(async move {
+            let mut sway = match SwayIpc::connect(None, vec![EventType::BarStateUpdate]).await {
+                Ok(sway) => sway,
+                Err(err) => {
+                    eprintln!("Failed to connect to Sway socket. {}", err);
+                    return;
+                }
+            };
+            let mut hints = HashMap::new();
+            // Starts out as if Sway had just been connected to, so the config is read
+            let mut message = SwayMessage::Reconnected;
+            loop {
+                let modifier_held = match message {
+                    SwayMessage::Event(event) => match *event {
+                        Event::BarStateUpdate(bar_state) => Some(bar_state.visible_by_modifier),
+                        _ => None,
+                    },
+                    // The config may have changed while Sway was gone
+                    SwayMessage::Reconnected => {
+                        match sway.connection.get_config().await {
+                            Ok(config) => {
+                                hints = Self::get_hints(&config.config, &options, &key_led_map)
+                            }
+                            Err(err) => eprintln!("Failed to get the Sway config. {}", err),
+                        }
+                        Some(false)
+                    }
+                };
+                if let Some(modifier_held) = modifier_held {
+                    let render_output =
+                        Self::render(&mut sender, &module_leds, &hints, modifier_held).await;
+                    if let Err(err) = render_output {
+                        eprintln!("{}", err)
+                    };
+                }
+                message = tokio::select! {
+                    biased;
+                    _ = cancellation_token.cancelled() => {
+                        break;
+                    }
+                    message = sway.next_event() => message,
+                };
+            }
+        });
+    }
+}
+
+impl ShortcutHintsModule {
+    /// The color of every LED that has a binding with the modifier in the options
+    fn get_hints(
+        config: &str,
+        options: &ShortcutHintsModuleOptions,
+        key_led_map: &HashMap<KeyId, u32>,
+    ) -> HashMap<u32, Color> {
+        let modifier = sway_config::normalize_modifier(&options.modifier);
+        let bindings = sway_config::parse_bindings(config)
+            .into_iter()
+            .filter(|binding| binding.mode == "default" && binding.modifiers.contains(&modifier))
+            .collect::<Vec<SwayBinding>>();
+        let mut hints = HashMap::new();
+        // Bindings with only the modifier take precedence over the ones that need more modifiers
+        // on the same key, since those are the ones that run if nothing else is held
+        for only_modifier in [true, false] {
+            for binding in &bindings {
+                if (binding.modifiers.len() == 1) != only_modifier {
+                    continue;
+                }
+                let Some(&led) = sway_config::keysym_to_key_id(&binding.key)
+                    .and_then(|key| key_led_map.get(&key))
+                else {
+                    continue;
+                };
+                hints
+                    .entry(led)
+                    .or_insert_with(|| options.binding_color(binding));
+            }
+        }
+        hints
+    }
+
+    async fn render(
+        sender: &mut Sender<KeyboardControllerMessage>,
+        module_leds: &[Option<u32>],
+        hints: &HashMap<u32, Color>,
+        modifier_held: bool,
+    ) -> anyhow::Result<()> {
+        for led in module_leds.iter().flatten() {
+            let color = match hints.get(led) {
+                Some(&color) if modifier_held => color,
+                _ => Color::new(0, 0, 0),
+            };
+            KeyboardController::update_led_urgent(sender, *led, color).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_binding_color() {
+        let options = ShortcutHintsModuleOptions::default();
+        let binding = |command: &str| SwayBinding {
+            mode: "default".to_owned(),
+            modifiers: vec!["Mod4".to_owned()],
+            key: "1".to_owned(),
+            command: command.to_owned(),
+        };
+        assert_eq!(
+            options.binding_color(&binding("workspace number 1")),
+            options.workspace_color
+        );
+        assert_eq!(
+            options.binding_color(&binding("move container to workspace number 1")),
+            options.move_color
+        );
+        assert_eq!(
+            options.binding_color(&binding("move workspace to output left")),
+            options.workspace_color
+        );
+        assert_eq!(
+            options.binding_color(&binding("exec foot")),
+            options.exec_color
+        );
+        assert_eq!(
+            options.binding_color(&binding("focus left")),
+            options.focus_color
+        );
+        assert_eq!(
+            options.binding_color(&binding("move left")),
+            options.move_color
+        );
+        assert_eq!(options.binding_color(&binding("kill")), options.other_color);
+    }
+
+    #[test]
+    fn test_get_hints() {
+        let config = "
+set $mod Mod4
+bindsym $mod+Shift+q kill
+bindsym $mod+q exec foot
+bindsym $mod+Shift+h move left
+bindsym $mod+1 workspace number 1
+bindsym Mod1+f focus left
+bindsym Logo+Return exec foot
+mode \"resize\" {
+    bindsym $mod+h resize shrink width 10px
+}
+";
+        let key_led_map = HashMap::from([
+            (KeyId::Char('q'), 0),
+            (KeyId::Char('h'), 1),
+            (KeyId::Char('1'), 2),
+            (KeyId::Char('f'), 3),
+            (KeyId::Enter, 4),
+        ]);
+        let options = ShortcutHintsModuleOptions::default();
+        assert_eq!(
+            ShortcutHintsModule::get_hints(config, &options, &key_led_map),
+            HashMap::from([
+                // $mod+q comes after $mod+Shift+q, but wins since it needs only the modifier
+                (0, options.exec_color),
+                // Only $mod+Shift+h is bound in the default mode
+                (1, options.move_color),
+                (2, options.workspace_color),
+                (4, options.exec_color),
+            ])
+        );
+        let alt = ShortcutHintsModuleOptions {
+            modifier: "Alt".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(
+            ShortcutHintsModule::get_hints(config, &alt, &key_led_map),
+            HashMap::from([(3, alt.focus_color)])
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::time::{Duration, Instant};
+
+use openrgb::data::Color;
+use rgb::{ComponentMap, RGB8};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+use crate::core::audio::{AudioDevice, AudioSource, AudioState, PactlAudio};
+use crate::core::constants;
+use crate::core::keyboard_controller::{KeyboardController, KeyboardControllerMessage};
+use crate::core::progress_bar::ProgressBar;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub(crate) struct VolumeModuleOptions {
+    pub(crate) volume_color: RGB8,
+    /// All LEDs are lit in this color while muted
+    pub(crate) muted_color: RGB8,
+    /// How long the volume is shown at full brightness after it changes
+    pub(crate) show_duration: Duration,
+    /// How long it then takes to fade back to `idle_brightness`
+    pub(crate) fade_duration: Duration,
+    /// The brightness from 0.0 to 1.0 when the volume hasn't changed in a while. At 0.0 the LEDs
+    /// are only lit right after a change
+    pub(crate) idle_brightness: f32,
+}
+
+impl Default for VolumeModuleOptions {
+    fn default() -> Self {
+        Self {
+            volume_color: constants::DEFAULT_VOLUME_COLOR,
+            muted_color: constants::DEFAULT_MUTED_COLOR,
+            show_duration: Duration::from_millis(1500),
+            fade_duration: Duration::from_millis(500),
+            idle_brightness: 0.,
+        }
+    }
+}
+
+impl VolumeModuleOptions {
+    /// The brightness of the module `since_change` after the volume or mute state changed
+    fn brightness(&self, since_change: Duration) -> f32 {
+        if since_change < self.show_duration {
+            return 1.;
+        }
+        let fade_progress = if self.fade_duration.is_zero() {
+            1.
+        } else {
+            ((since_change - self.show_duration).as_secs_f32() / self.fade_duration.as_secs_f32())
+                .min(1.)
+        };
+        1. - (1. - self.idle_brightness) * fade_progress
+    }
+}
+
+pub(crate) struct VolumeModule {}
+
+impl VolumeModule {
+    pub fn run(
+        task_tracker: &TaskTracker,
+        cancellation_token: CancellationToken,
+        sender: Sender<KeyboardControllerMessage>,
+        module_leds: Vec<Option<u32>>,
+        options: VolumeModuleOptions,
+    ) {
+        task_tracker.spawn(async move {
+            let mut pactl = match PactlAudio::new(AudioDevice::Sink) {
+                Ok(pactl) => pactl,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return;
+                }
+            };
+            Self::run_with_source(
+                &mut pactl,
+                cancellation_token,
+                sender,
+                &module_leds,
+                &options,
+            )
+            .await
+        });
+    }
+
+    async fn run_with_source(
+        source: &mut impl AudioSource,
+        cancellation_token: CancellationToken,
+        mut sender: Sender<KeyboardControllerMessage>,
+        module_leds: &[Option<u32>],
+        options: &VolumeModuleOptions,
+    ) {
+        let mut state = match source.get_state().await {
+            Ok(state) => state,
+            Err(err) => {
+                eprintln!("Failed to get the volume. {}", err);
+                return;
+            }
+        };
+        // The volume is shown once at the start, so it's clear where the module is
+        let mut last_change = Instant::now();
+        loop {
+            let brightness = options.brightness(last_change.elapsed());
+            for (led, color) in led_colors(module_leds, state, brightness, options) {
+                if let Err(err) = KeyboardController::update_led(&mut sender, led, color).await {
+                    eprintln!("{}", err)
+                }
+            }
+
+            let fading = last_change.elapsed() < options.show_duration + options.fade_duration;
+            tokio::select! {
+                biased;
+                _ = cancellation_token.cancelled() => {
+                    break;
+                }
+                change = source.wait_for_change() => {
+                    if let Err(err) = change {
+                        eprintln!("{}", err);
+                        break;
+                    }
+                }
+                // Nothing changes once the fade is over
+                _ = tokio::time::sleep(constants::VOLUME_ANIMATION_FRAME_TIME), if fading => {
+                    continue;
+                }
+            };
+            match source.get_state().await {
+                Ok(new_state) if new_state != state => {
+                    state = new_state;
+                    last_change = Instant::now();
+                }
+                Ok(_) => {}
+                Err(err) => eprintln!("Failed to get the volume. {}", err),
+            }
+        }
+    }
+}
+
+/// The volume as a bar, or all LEDs in the muted color
+fn led_colors(
+    module_leds: &[Option<u32>],
+    state: AudioState,
+    brightness: f32,
+    options: &VolumeModuleOptions,
+) -> Vec<(u32, Color)> {
+    let dim = |color: RGB8, brightness: f32| color.map(|comp| (comp as f32 * brightness) as u8);
+    if state.muted {
+        return module_leds
+            .iter()
+            .flatten()
+            .map(|&led| (led, dim(options.muted_color, brightness)))
+            .collect();
+    }
+    ProgressBar::default()
+        .render(module_leds, state.volume.min(1.))
+        .into_iter()
+        .map(|(led, fill)| (led, dim(options.volume_color, fill * brightness)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use openrgb::data::Color;
+
+    use super::*;
+    use crate::core::scripted::{run_until, Scripted};
+
+    #[test]
+    fn test_brightness() {
+        let options = VolumeModuleOptions {
+            show_duration: Duration::from_secs(1),
+            fade_duration: Duration::from_secs(1),
+            idle_brightness: 0.2,
+            ..Default::default()
+        };
+        assert_eq!(options.brightness(Duration::from_millis(500)), 1.);
+        assert!((options.brightness(Duration::from_millis(1500)) - 0.6).abs() < 1e-6);
+        assert!((options.brightness(Duration::from_secs(5)) - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_led_colors() {
+        let options = VolumeModuleOptions::default();
+        let module_leds = [Some(0), None, Some(2), Some(3)];
+        let half = AudioState {
+            volume: 0.5,
+            muted: false,
+        };
+        assert_eq!(
+            led_colors(&module_leds, half, 1., &options),
+            vec![
+                (0, options.volume_color),
+                (2, Color::new(0, 0, 0)),
+                (3, Color::new(0, 0, 0)),
+            ]
+        );
+        let muted = AudioState {
+            volume: 0.5,
+            muted: true,
+        };
+        assert_eq!(
+            led_colors(&module_leds, muted, 0.5, &options),
+            vec![
+                (0, Color::new(127, 0, 0)),
+                (2, Color::new(127, 0, 0)),
+                (3, Color::new(127, 0, 0)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shows_muted_after_change() {
+        let mut source = Scripted::new(vec![
+            AudioState {
+                volume: 1.,
+                muted: false,
+            },
+            AudioState {
+                volume: 1.,
+                muted: true,
+            },
+        ]);
+        let options = VolumeModuleOptions::default();
+        let messages = run_until(
+            |cancellation_token, sender| {
+                VolumeModule::run_with_source(
+                    &mut source,
+                    cancellation_token,
+                    sender,
+                    &[Some(0)],
+                    &options,
+                )
+            },
+            |messages| {
+                messages
+                    .last()
+                    .is_some_and(|message| message.color() == options.muted_color)
+            },
+        )
+        .await;
+        assert_eq!(messages[0].color(), options.volume_color);
+        assert!(messages.iter().all(|message| message.led() == Some(0)));
+    }
+}
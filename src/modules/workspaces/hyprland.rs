@@ -0,0 +1,285 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::UnixStream;
+
+use super::{WorkspaceSource, WorkspaceState};
+
+/// Events from `.socket2.sock` that can change the state of any workspace
+const WORKSPACE_EVENTS: [&str; 13] = [
+    "workspace",
+    "focusedmon",
+    "activewindowv2",
+    "createworkspace",
+    "destroyworkspace",
+    "moveworkspace",
+    "renameworkspace",
+    "monitoradded",
+    "monitorremoved",
+    "openwindow",
+    "closewindow",
+    "movewindow",
+    "urgent",
+];
+
+#[derive(Deserialize)]
+struct HyprlandWorkspace {
+    id: i32,
+    name: String,
+    monitor: String,
+}
+
+#[derive(Deserialize)]
+struct HyprlandWorkspaceRef {
+    id: i32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HyprlandMonitor {
+    active_workspace: HyprlandWorkspaceRef,
+    focused: bool,
+}
+
+#[derive(Deserialize)]
+struct HyprlandClient {
+    address: String,
+    class: String,
+    workspace: HyprlandWorkspaceRef,
+}
+
+/// Workspaces from Hyprland. Events are read from `.socket2.sock` and the state is queried over
+/// `.socket.sock`, which takes one request per connection
+pub(crate) struct HyprlandWorkspaces {
+    request_socket: PathBuf,
+    events: Lines<BufReader<UnixStream>>,
+    /// Hyprland only tells us when a window becomes urgent, so we have to keep track of it until
+    /// the workspace with the window is focused
+    urgent_windows: HashSet<String>,
+}
+
+impl HyprlandWorkspaces {
+    /// Whether we are running under Hyprland
+    pub(crate) fn is_running() -> bool {
+        env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some()
+    }
+
+    pub(crate) async fn connect() -> anyhow::Result<Self> {
+        let signature = env::var("HYPRLAND_INSTANCE_SIGNATURE")
+            .context("HYPRLAND_INSTANCE_SIGNATURE is not set")?;
+        // Newer versions of Hyprland keep the sockets in the runtime dir, older ones in /tmp
+        let socket_dir = env::var("XDG_RUNTIME_DIR")
+            .map(|runtime_dir| Path::new(&runtime_dir).join("hypr").join(&signature))
+            .ok()
+            .filter(|socket_dir| socket_dir.exists())
+            .unwrap_or_else(|| Path::new("/tmp/hypr").join(&signature));
+        Self::connect_to(&socket_dir).await
+    }
+
+    pub(crate) async fn connect_to(socket_dir: &Path) -> anyhow::Result<Self> {
+        let event_stream = UnixStream::connect(socket_dir.join(".socket2.sock"))
+            .await
+            .context("Failed to connect to the Hyprland event socket")?;
+        Ok(Self {
+            request_socket: socket_dir.join(".socket.sock"),
+            events: BufReader::new(event_stream).lines(),
+            urgent_windows: HashSet::new(),
+        })
+    }
+
+    async fn request<T: for<'de> Deserialize<'de>>(&self, command: &str) -> anyhow::Result<T> {
+        let mut stream = UnixStream::connect(&self.request_socket)
+            .await
+            .context("Failed to connect to the Hyprland socket")?;
+        stream
+            .write_all(format!("j/{}", command).as_bytes())
+            .await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        serde_json::from_str(&response)
+            .with_context(|| format!("Invalid reply from Hyprland to {}", command))
+    }
+}
+
+impl WorkspaceSource for HyprlandWorkspaces {
+    async fn get_workspaces(&mut self) -> anyhow::Result<Vec<WorkspaceState>> {
+        let workspaces: Vec<HyprlandWorkspace> = self.request("workspaces").await?;
+        let monitors: Vec<HyprlandMonitor> = self.request("monitors").await?;
+        let clients: Vec<HyprlandClient> = self.request("clients").await?;
+
+        let focused_workspace = monitors
+            .iter()
+            .find(|monitor| monitor.focused)
+            .map(|monitor| monitor.active_workspace.id);
+        // Windows that were closed or whose workspace has been focused aren't urgent anymore
+        self.urgent_windows.retain(|address| {
+            clients.iter().any(|client| {
+                same_address(&client.address, address)
+                    && Some(client.workspace.id) != focused_workspace
+            })
+        });
+
+        let mut windows_by_workspace: HashMap<i32, Vec<Option<String>>> = HashMap::new();
+        for client in &clients {
+            windows_by_workspace
+                .entry(client.workspace.id)
+                .or_default()
+                .push(Some(client.class.clone()));
+        }
+
+        Ok(workspaces
+            .into_iter()
+            .map(|workspace| WorkspaceState {
+                id: workspace.id as i64,
+                name: workspace.name,
+                num: workspace.id,
+                focused: Some(workspace.id) == focused_workspace,
+                visible: monitors
+                    .iter()
+                    .any(|monitor| monitor.active_workspace.id == workspace.id),
+                urgent: clients.iter().any(|client| {
+                    client.workspace.id == workspace.id
+                        && self
+                            .urgent_windows
+                            .iter()
+                            .any(|address| same_address(&client.address, address))
+                }),
+                windows: windows_by_workspace
+                    .remove(&workspace.id)
+                    .unwrap_or_default(),
+                output: workspace.monitor,
+            })
+            .collect())
+    }
+
+    /// Waits for an event that can change the workspaces
+    async fn wait_for_change(&mut self) -> anyhow::Result<()> {
+        loop {
+            let Some(line) = self.events.next_line().await? else {
+                bail!("The Hyprland event socket was closed");
+            };
+            let Some((event, data)) = line.split_once(">>") else {
+                continue;
+            };
+            if event == "urgent" {
+                self.urgent_windows.insert(data.to_owned());
+            }
+            if WORKSPACE_EVENTS.contains(&event) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Events leave out the 0x prefix that the replies to requests have
+fn same_address(address1: &str, address2: &str) -> bool {
+    address1.trim_start_matches("0x") == address2.trim_start_matches("0x")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixListener;
+
+    use super::HyprlandWorkspaces;
+    use crate::modules::workspaces::{WorkspaceSource, WorkspaceState};
+
+    /// Serves canned replies on the request socket and the given events on the event socket
+    fn fake_hyprland(name: &str, events: &'static str) -> PathBuf {
+        let socket_dir = std::env::temp_dir().join(format!(
+            "keyboard-indicators-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&socket_dir);
+        std::fs::create_dir_all(&socket_dir).unwrap();
+
+        let request_listener = UnixListener::bind(socket_dir.join(".socket.sock")).unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = request_listener.accept().await.unwrap();
+                let mut request = [0; 64];
+                let len = stream.read(&mut request).await.unwrap();
+                let reply = match &request[..len] {
+                    b"j/workspaces" => {
+                        r#"[{"id": 1, "name": "1", "monitor": "DP-1", "windows": 2},
+                            {"id": 2, "name": "2", "monitor": "HDMI-A-1", "windows": 1},
+                            {"id": 3, "name": "3", "monitor": "DP-1", "windows": 1}]"#
+                    }
+                    b"j/monitors" => {
+                        r#"[{"name": "DP-1", "activeWorkspace": {"id": 1, "name": "1"}, "focused": true},
+                            {"name": "HDMI-A-1", "activeWorkspace": {"id": 2, "name": "2"}, "focused": false}]"#
+                    }
+                    b"j/clients" => {
+                        r#"[{"address": "0xa1", "class": "firefox", "workspace": {"id": 1, "name": "1"}},
+                            {"address": "0xa2", "class": "foot", "workspace": {"id": 1, "name": "1"}},
+                            {"address": "0xb1", "class": "discord", "workspace": {"id": 2, "name": "2"}},
+                            {"address": "0xc1", "class": "foot", "workspace": {"id": 3, "name": "3"}}]"#
+                    }
+                    _ => "unknown request",
+                };
+                stream.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+
+        let event_listener = UnixListener::bind(socket_dir.join(".socket2.sock")).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = event_listener.accept().await.unwrap();
+            stream.write_all(events.as_bytes()).await.unwrap();
+            // Keep the socket open, like Hyprland does
+            std::future::pending::<()>().await;
+        });
+        socket_dir
+    }
+
+    #[tokio::test]
+    async fn test_hyprland_workspaces() {
+        let socket_dir = fake_hyprland("workspaces", "activelayout>>kbd,us\nurgent>>c1\n");
+        let mut hyprland = HyprlandWorkspaces::connect_to(&socket_dir).await.unwrap();
+        hyprland.wait_for_change().await.unwrap();
+        let workspaces = hyprland.get_workspaces().await.unwrap();
+        std::fs::remove_dir_all(&socket_dir).unwrap();
+
+        assert_eq!(
+            workspaces,
+            vec![
+                WorkspaceState {
+                    id: 1,
+                    name: "1".to_owned(),
+                    num: 1,
+                    output: "DP-1".to_owned(),
+                    focused: true,
+                    visible: true,
+                    urgent: false,
+                    windows: vec![Some("firefox".to_owned()), Some("foot".to_owned())],
+                },
+                WorkspaceState {
+                    id: 2,
+                    name: "2".to_owned(),
+                    num: 2,
+                    output: "HDMI-A-1".to_owned(),
+                    focused: false,
+                    visible: true,
+                    urgent: false,
+                    windows: vec![Some("discord".to_owned())],
+                },
+                WorkspaceState {
+                    id: 3,
+                    name: "3".to_owned(),
+                    num: 3,
+                    output: "DP-1".to_owned(),
+                    focused: false,
+                    visible: false,
+                    urgent: true,
+                    windows: vec![Some("foot".to_owned())],
+                },
+            ]
+        );
+    }
+}
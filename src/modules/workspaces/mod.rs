@@ -0,0 +1,638 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::Sender;
+
+use anyhow::Context;
+use openrgb::data::Color;
+use rgb::{ComponentMap, RGB, RGB8, RGBA8};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+use crate::core::keyboard_controller::{KeyboardController, KeyboardControllerMessage};
+use crate::core::{constants, utils};
+
+use self::hyprland::HyprlandWorkspaces;
+use self::sway::SwayWorkspaces;
+
+mod hyprland;
+mod sway;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct WorkspacesModuleOptions {
+    /// Tints for workspaces that are visible but not focused, keyed by the name of the output
+    /// they are on. Outputs without a tint use `VISIBLE_WORKSPACE_OVERLAY`
+    #[serde(default)]
+    pub(crate) output_tints: HashMap<String, RGB8>,
+    #[serde(default)]
+    pub(crate) window_count_display: WindowCountDisplay,
+    /// The number of windows at which a workspace is shown at full brightness or fill
+    #[serde(default = "default_max_window_count")]
+    pub(crate) max_window_count: u32,
+    #[serde(default)]
+    pub(crate) urgent_animation: UrgentAnimation,
+    #[serde(default = "default_urgent_color")]
+    pub(crate) urgent_color: RGB8,
+    /// The time one cycle of the urgent animation takes
+    #[serde(default = "default_urgent_animation_period")]
+    pub(crate) urgent_animation_period: Duration,
+}
+
+impl Default for WorkspacesModuleOptions {
+    fn default() -> Self {
+        Self {
+            output_tints: HashMap::new(),
+            window_count_display: WindowCountDisplay::default(),
+            max_window_count: default_max_window_count(),
+            urgent_animation: UrgentAnimation::default(),
+            urgent_color: default_urgent_color(),
+            urgent_animation_period: default_urgent_animation_period(),
+        }
+    }
+}
+
+impl WorkspacesModuleOptions {
+    /// The brightness of the urgent animation `elapsed` after it started. The animation repeats
+    /// every period, and a period of 0 stops it at its start
+    fn urgent_brightness(&self, elapsed: Duration) -> f32 {
+        let period = self.urgent_animation_period.as_secs_f32();
+        let progress = if period > 0. {
+            (elapsed.as_secs_f32() / period).fract()
+        } else {
+            0.
+        };
+        self.urgent_animation.brightness(progress)
+    }
+}
+
+fn default_max_window_count() -> u32 {
+    4
+}
+
+fn default_urgent_color() -> RGB8 {
+    constants::URGENT_WORKSPACE_COLOR
+}
+
+fn default_urgent_animation_period() -> Duration {
+    Duration::from_secs(1)
+}
+
+/// How the number of windows on a workspace is shown
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) enum WindowCountDisplay {
+    #[default]
+    Off,
+    /// The workspace key gets brighter the more windows there are
+    Brightness,
+    /// The module LEDs are split into two rows, separated by two unbound LEDs. The LED in the
+    /// second row below a workspace key is filled according to the number of windows
+    SecondRow,
+}
+
+impl WindowCountDisplay {
+    pub(crate) fn all() -> [WindowCountDisplay; 3] {
+        [
+            WindowCountDisplay::Off,
+            WindowCountDisplay::Brightness,
+            WindowCountDisplay::SecondRow,
+        ]
+    }
+}
+
+/// How the key of an urgent workspace is animated until the urgent flag clears
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) enum UrgentAnimation {
+    /// The key is lit in the urgent color without any animation
+    #[default]
+    Static,
+    /// On for the first half of the period and off for the second
+    Blink,
+    /// Lights up at the start of the period and fades out
+    Pulse,
+    /// Fades smoothly in and out
+    Breathe,
+}
+
+impl UrgentAnimation {
+    pub(crate) fn all() -> [UrgentAnimation; 4] {
+        [
+            UrgentAnimation::Static,
+            UrgentAnimation::Blink,
+            UrgentAnimation::Pulse,
+            UrgentAnimation::Breathe,
+        ]
+    }
+
+    /// The brightness from 0.0 to 1.0 at `progress` (0.0 to 1.0) through the animation period
+    pub(crate) fn brightness(&self, progress: f32) -> f32 {
+        match self {
+            UrgentAnimation::Static => 1.,
+            UrgentAnimation::Blink => {
+                if progress < 0.5 {
+                    1.
+                } else {
+                    0.
+                }
+            }
+            UrgentAnimation::Pulse => (1. - progress).powi(2),
+            UrgentAnimation::Breathe => (1. - (progress * 2. * std::f32::consts::PI).cos()) / 2.,
+        }
+    }
+}
+
+/// The state of a workspace, the same for every compositor
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct WorkspaceState {
+    /// Unique for the workspace as long as it exists
+    pub(crate) id: i64,
+    pub(crate) name: String,
+    /// Decides which key the workspace is shown on. Workspace 1 is on the first key
+    pub(crate) num: i32,
+    pub(crate) output: String,
+    pub(crate) focused: bool,
+    pub(crate) visible: bool,
+    pub(crate) urgent: bool,
+    /// The app id of every window on the workspace, including floating and nested ones. None for
+    /// windows without one (XWayland). On i3 and Hyprland, this is the window class
+    pub(crate) windows: Vec<Option<String>>,
+}
+
+/// Somewhere to get the state of all workspaces from, like a compositor
+pub(crate) trait WorkspaceSource {
+    fn get_workspaces(
+        &mut self,
+    ) -> impl Future<Output = anyhow::Result<Vec<WorkspaceState>>> + Send;
+
+    /// Waits until something happens that can change the workspaces
+    fn wait_for_change(&mut self) -> impl Future<Output = anyhow::Result<()>> + Send;
+}
+
+pub(crate) struct WorkspacesModule {}
+
+impl WorkspacesModule {
+    pub fn run(
+        task_tracker: &TaskTracker,
+        cancellation_token: CancellationToken,
+        sender: Sender<KeyboardControllerMessage>,
+        leds_order: Vec<Option<u32>>,
+        options: WorkspacesModuleOptions,
+    ) {
+        task_tracker.spawn(async move {
+            if HyprlandWorkspaces::is_running() {
+                match HyprlandWorkspaces::connect().await {
+                    Ok(mut hyprland) => {
+                        println!("Connected to Hyprland");
+                        Self::run_with_source(
+                            &mut hyprland,
+                            cancellation_token,
+                            sender,
+                            &leds_order,
+                            &options,
+                        )
+                        .await
+                    }
+                    Err(err) => eprintln!("Failed to connect to Hyprland. {}", err),
+                }
+            } else {
+                match SwayWorkspaces::connect().await {
+                    Ok(mut sway) => {
+                        println!("Subscribed to {} events", sway.name());
+                        Self::run_with_source(
+                            &mut sway,
+                            cancellation_token,
+                            sender,
+                            &leds_order,
+                            &options,
+                        )
+                        .await
+                    }
+                    Err(err) => eprintln!("Failed to connect to Sway or i3. {}", err),
+                }
+            }
+        });
+    }
+
+    async fn run_with_source(
+        source: &mut impl WorkspaceSource,
+        cancellation_token: CancellationToken,
+        mut sender: Sender<KeyboardControllerMessage>,
+        leds_order: &[Option<u32>],
+        options: &WorkspacesModuleOptions,
+    ) {
+        let mut workspaces = Self::refresh(&mut sender, source, leds_order, options)
+            .await
+            .unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                Vec::new()
+            });
+        let animation_start = Instant::now();
+        loop {
+            let animating = options.urgent_animation != UrgentAnimation::Static
+                && workspaces.iter().any(|workspace| workspace.urgent);
+            tokio::select! {
+                biased;
+                _ = cancellation_token.cancelled() => {
+                    break;
+                }
+                change = source.wait_for_change() => {
+                    if let Err(err) = change {
+                        eprintln!("{}", err);
+                        break;
+                    }
+                }
+                _ = tokio::time::sleep(constants::URGENT_ANIMATION_FRAME_TIME), if animating => {
+                    let animation_output = Self::render_urgent(
+                        &mut sender,
+                        leds_order,
+                        &workspaces,
+                        options,
+                        animation_start.elapsed(),
+                    )
+                    .await;
+                    if let Err(err) = animation_output {
+                        eprintln!("{}", err)
+                    };
+                    continue;
+                }
+            };
+            match Self::refresh(&mut sender, source, leds_order, options).await {
+                Ok(new_workspaces) => workspaces = new_workspaces,
+                Err(err) => eprintln!("{}", err),
+            };
+        }
+    }
+}
+
+impl WorkspacesModule {
+    /// Queries the current state of all workspaces and redraws every workspace key
+    async fn refresh(
+        sender: &mut Sender<KeyboardControllerMessage>,
+        source: &mut impl WorkspaceSource,
+        leds_order: &[Option<u32>],
+        options: &WorkspacesModuleOptions,
+    ) -> anyhow::Result<Vec<WorkspaceState>> {
+        let workspaces = source
+            .get_workspaces()
+            .await
+            .context("Failed to get workspaces from the compositor")?;
+        Self::render(sender, leds_order, &workspaces, options).await?;
+        Ok(workspaces)
+    }
+
+    async fn render(
+        sender: &mut Sender<KeyboardControllerMessage>,
+        leds_order: &[Option<u32>],
+        workspaces: &[WorkspaceState],
+        options: &WorkspacesModuleOptions,
+    ) -> anyhow::Result<()> {
+        for (led_index, color) in led_colors(leds_order, workspaces, options) {
+            KeyboardController::update_led(sender, led_index, color).await?;
+        }
+        Ok(())
+    }
+
+    /// Draws the next frame of the urgent animation on the keys of all urgent workspaces
+    async fn render_urgent(
+        sender: &mut Sender<KeyboardControllerMessage>,
+        leds_order: &[Option<u32>],
+        workspaces: &[WorkspaceState],
+        options: &WorkspacesModuleOptions,
+        elapsed: Duration,
+    ) -> anyhow::Result<()> {
+        let brightness = options.urgent_brightness(elapsed);
+        let color = options
+            .urgent_color
+            .map(|comp| (comp as f32 * brightness) as u8);
+        let rows = workspace_rows(leds_order, options);
+        for workspace in workspaces.iter().filter(|workspace| workspace.urgent) {
+            let Some(Some(led_index)) = rows[0].get((workspace.num - 1) as usize) else {
+                continue;
+            };
+            KeyboardController::update_led(sender, *led_index, color).await?;
+        }
+        Ok(())
+    }
+}
+
+/// The color of every LED in the module for the given workspaces
+fn led_colors(
+    leds_order: &[Option<u32>],
+    workspaces: &[WorkspaceState],
+    options: &WorkspacesModuleOptions,
+) -> Vec<(u32, Color)> {
+    let rows = workspace_rows(leds_order, options);
+    let window_count_leds = rows.get(1).map_or(&[][..], Vec::as_slice);
+    let mut colors = Vec::new();
+
+    for (i, led_index) in rows[0].iter().enumerate() {
+        // Workspaces that don't exist are empty
+        let workspace = workspaces
+            .iter()
+            .find(|workspace| workspace.num == i as i32 + 1);
+        if let Some(led_index) = led_index {
+            let color = workspace.map_or(constants::EMPTY_WORKSPACE_COLOR, |workspace| {
+                workspace_color(workspace, options)
+            });
+            colors.push((*led_index, color));
+        }
+        if let Some(Some(led_index)) = window_count_leds.get(i) {
+            let color = workspace.map_or(constants::EMPTY_WORKSPACE_COLOR, |workspace| {
+                let fill = window_count_fraction(workspace, options);
+                app_colors_average(workspace).map(|comp| (comp as f32 * fill) as u8)
+            });
+            colors.push((*led_index, color));
+        }
+    }
+    colors
+}
+
+/// The first row holds the workspace keys, the second the window count LEDs if they are used
+fn workspace_rows(
+    leds_order: &[Option<u32>],
+    options: &WorkspacesModuleOptions,
+) -> Vec<Vec<Option<u32>>> {
+    if options.window_count_display == WindowCountDisplay::SecondRow {
+        utils::split_rows(leds_order)
+    } else {
+        vec![leds_order.to_vec()]
+    }
+}
+
+fn app_color(app_id: Option<&str>) -> Option<Color> {
+    match app_id {
+        Some("") => Some(constants::SPOTIFY_WORKSPACE_COLOR),
+        None => Some(constants::DISCORD_WORKSPACE_COLOR),
+        Some("firefox") => Some(constants::FIREFOX_WORKSPACE_COLOR),
+        // Window classes, as used on i3 and Hyprland
+        Some("Spotify" | "spotify") => Some(constants::SPOTIFY_WORKSPACE_COLOR),
+        Some("discord") => Some(constants::DISCORD_WORKSPACE_COLOR),
+        _ => None,
+    }
+}
+
+/// How full the window count indicator of the workspace is, from 0.0 to 1.0
+fn window_count_fraction(workspace: &WorkspaceState, options: &WorkspacesModuleOptions) -> f32 {
+    (workspace.windows.len() as f32 / options.max_window_count.max(1) as f32).min(1.)
+}
+
+fn app_colors_average(workspace: &WorkspaceState) -> Color {
+    let all_app_colors = workspace
+        .windows
+        .iter()
+        .filter_map(|app_id| app_color(app_id.as_deref()))
+        .collect::<Vec<Color>>();
+
+    let mut average_color_u32 = RGB::new(0_u32, 0, 0);
+    for app_color in all_app_colors.iter() {
+        average_color_u32 += app_color.map(|comp| comp as u32);
+    }
+
+    if !all_app_colors.is_empty() {
+        average_color_u32.map(|comp| (comp / all_app_colors.len() as u32) as u8)
+    } else {
+        constants::UNFOCUSED_DEFAULT_WORKSPACE_COLOR
+    }
+}
+
+pub(crate) fn workspace_color(
+    workspace: &WorkspaceState,
+    options: &WorkspacesModuleOptions,
+) -> Color {
+    if workspace.urgent {
+        return options.urgent_color;
+    }
+
+    let mut average_color = app_colors_average(workspace);
+    if options.window_count_display == WindowCountDisplay::Brightness {
+        let brightness = constants::MIN_WINDOW_COUNT_BRIGHTNESS
+            + (1. - constants::MIN_WINDOW_COUNT_BRIGHTNESS)
+                * window_count_fraction(workspace, options);
+        average_color = average_color.map(|comp| (comp as f32 * brightness) as u8);
+    }
+
+    let overlay = if workspace.focused {
+        constants::CURRENT_WORKSPACE_OVERLAY
+    } else if workspace.visible {
+        options.output_tints.get(&workspace.output).map_or(
+            constants::VISIBLE_WORKSPACE_OVERLAY,
+            |tint| {
+                RGBA8::new(
+                    tint.r,
+                    tint.g,
+                    tint.b,
+                    constants::VISIBLE_WORKSPACE_OVERLAY.a,
+                )
+            },
+        )
+    } else {
+        constants::UNFOCUSED_WORKSPACE_OVERLAY
+    };
+    utils::overlay(overlay, average_color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::scripted::{run_until, Scripted};
+
+    impl WorkspaceSource for Scripted<Vec<WorkspaceState>> {
+        async fn get_workspaces(&mut self) -> anyhow::Result<Vec<WorkspaceState>> {
+            Ok(self.current().clone())
+        }
+
+        async fn wait_for_change(&mut self) -> anyhow::Result<()> {
+            self.next().await;
+            Ok(())
+        }
+    }
+
+    fn workspace(num: i32, focused: bool, visible: bool, windows: &[&str]) -> WorkspaceState {
+        WorkspaceState {
+            id: num as i64,
+            name: num.to_string(),
+            num,
+            output: "DP-1".to_owned(),
+            focused,
+            visible,
+            urgent: false,
+            windows: windows
+                .iter()
+                .map(|app_id| Some(app_id.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_led_colors() {
+        let options = WorkspacesModuleOptions::default();
+        let workspaces = vec![
+            workspace(1, true, true, &["firefox"]),
+            workspace(3, false, false, &["foot"]),
+        ];
+        assert_eq!(
+            led_colors(&[Some(10), None, Some(12), Some(13)], &workspaces, &options),
+            vec![
+                (
+                    10,
+                    utils::overlay(
+                        constants::CURRENT_WORKSPACE_OVERLAY,
+                        constants::FIREFOX_WORKSPACE_COLOR
+                    )
+                ),
+                (
+                    12,
+                    utils::overlay(
+                        constants::UNFOCUSED_WORKSPACE_OVERLAY,
+                        constants::UNFOCUSED_DEFAULT_WORKSPACE_COLOR
+                    )
+                ),
+                (13, constants::EMPTY_WORKSPACE_COLOR),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_visible_workspace_tints() {
+        let tint = RGB8::new(0, 40, 120);
+        let options = WorkspacesModuleOptions {
+            output_tints: HashMap::from([("HDMI-A-1".to_owned(), tint)]),
+            ..Default::default()
+        };
+        // Both are visible on their outputs, but the focus is on a third one
+        let mut tinted = workspace(2, false, true, &["foot"]);
+        tinted.output = "HDMI-A-1".to_owned();
+        let untinted = workspace(3, false, true, &["foot"]);
+        let app_color = app_colors_average(&untinted);
+        assert_eq!(
+            workspace_color(&tinted, &options),
+            utils::overlay(
+                RGBA8::new(
+                    tint.r,
+                    tint.g,
+                    tint.b,
+                    constants::VISIBLE_WORKSPACE_OVERLAY.a
+                ),
+                app_color
+            )
+        );
+        assert_eq!(
+            workspace_color(&untinted, &options),
+            utils::overlay(constants::VISIBLE_WORKSPACE_OVERLAY, app_color)
+        );
+        assert_ne!(
+            workspace_color(&tinted, &options),
+            workspace_color(&untinted, &options)
+        );
+    }
+
+    #[test]
+    fn test_led_colors_second_row() {
+        let options = WorkspacesModuleOptions {
+            window_count_display: WindowCountDisplay::SecondRow,
+            max_window_count: 2,
+            ..Default::default()
+        };
+        let workspaces = vec![workspace(1, false, false, &["firefox", "firefox"])];
+        let colors = led_colors(
+            &[Some(0), Some(1), None, None, Some(10), Some(11)],
+            &workspaces,
+            &options,
+        );
+        assert!(colors.contains(&(10, constants::FIREFOX_WORKSPACE_COLOR)));
+        assert!(colors.contains(&(11, constants::EMPTY_WORKSPACE_COLOR)));
+    }
+
+    #[test]
+    fn test_urgent_animation_brightness() {
+        // A period of 2 seconds, at a quarter, half and three quarters through it, and a quarter
+        // through the second period
+        let brightness = |urgent_animation| {
+            let options = WorkspacesModuleOptions {
+                urgent_animation,
+                urgent_animation_period: Duration::from_secs(2),
+                ..Default::default()
+            };
+            [0, 500, 1000, 1500, 2500].map(|millis| {
+                (options.urgent_brightness(Duration::from_millis(millis)) * 1000.).round()
+            })
+        };
+        assert_eq!(
+            brightness(UrgentAnimation::Static),
+            [1000., 1000., 1000., 1000., 1000.]
+        );
+        assert_eq!(
+            brightness(UrgentAnimation::Blink),
+            [1000., 1000., 0., 0., 1000.]
+        );
+        assert_eq!(
+            brightness(UrgentAnimation::Pulse),
+            [1000., 563., 250., 63., 563.]
+        );
+        assert_eq!(
+            brightness(UrgentAnimation::Breathe),
+            [0., 500., 1000., 500., 500.]
+        );
+        let stopped = WorkspacesModuleOptions {
+            urgent_animation: UrgentAnimation::Pulse,
+            urgent_animation_period: Duration::ZERO,
+            ..Default::default()
+        };
+        assert_eq!(stopped.urgent_brightness(Duration::from_secs(3)), 1.);
+    }
+
+    #[tokio::test]
+    async fn test_redraws_on_change() {
+        let mut source = Scripted::new(vec![
+            vec![workspace(1, true, true, &[])],
+            vec![
+                workspace(1, false, true, &[]),
+                workspace(2, true, true, &[]),
+            ],
+        ]);
+        let options = WorkspacesModuleOptions::default();
+        let messages = run_until(
+            |cancellation_token, sender| {
+                WorkspacesModule::run_with_source(
+                    &mut source,
+                    cancellation_token,
+                    sender,
+                    &[Some(0), Some(1)],
+                    &options,
+                )
+            },
+            |messages| messages.len() == 4,
+        )
+        .await;
+        // Both keys are drawn once at the start and once after the change
+        assert_eq!(messages.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_window_opened() {
+        // A window opening only changes the windows of the workspace
+        let mut source = Scripted::new(vec![
+            vec![workspace(1, false, false, &["firefox"])],
+            vec![workspace(1, false, false, &["firefox", "foot"])],
+        ]);
+        let options = WorkspacesModuleOptions {
+            window_count_display: WindowCountDisplay::Brightness,
+            ..Default::default()
+        };
+        let messages = run_until(
+            |cancellation_token, sender| {
+                WorkspacesModule::run_with_source(
+                    &mut source,
+                    cancellation_token,
+                    sender,
+                    &[Some(0)],
+                    &options,
+                )
+            },
+            |messages| messages.len() == 2,
+        )
+        .await;
+        assert_ne!(messages[0].color(), messages[1].color());
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::env;
+
+use swayipc_async::{Event, EventType, Node, NodeType, Version, WindowChange};
+
+use crate::core::sway_ipc::{SwayIpc, SwayMessage};
+
+use super::{WorkspaceSource, WorkspaceState};
+
+/// Workspaces from Sway or i3, over their IPC socket. The two use the same protocol
+pub(crate) struct SwayWorkspaces {
+    sway: SwayIpc,
+    is_i3: bool,
+}
+
+impl SwayWorkspaces {
+    /// Connects to the socket in `SWAYSOCK` or, if it isn't set, `I3SOCK`. Without either,
+    /// swayipc asks i3 or Sway for their socket
+    pub(crate) async fn connect() -> anyhow::Result<Self> {
+        let socket_path = env::var_os("SWAYSOCK").or_else(|| env::var_os("I3SOCK"));
+        let mut sway = SwayIpc::connect(
+            socket_path,
+            vec![EventType::Workspace, EventType::Output, EventType::Window],
+        )
+        .await?;
+        let is_i3 = is_i3(&sway.connection.get_version().await?);
+        Ok(Self { sway, is_i3 })
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        if self.is_i3 {
+            "i3"
+        } else {
+            "Sway"
+        }
+    }
+}
+
+impl WorkspaceSource for SwayWorkspaces {
+    async fn get_workspaces(&mut self) -> anyhow::Result<Vec<WorkspaceState>> {
+        let workspaces = self.sway.connection.get_workspaces().await?;
+        let tree = self.sway.connection.get_tree().await?;
+        let mut windows_by_workspace = HashMap::new();
+        collect_windows_by_workspace(&tree, &mut windows_by_workspace);
+
+        Ok(workspaces
+            .into_iter()
+            .map(|workspace| WorkspaceState {
+                id: workspace.id,
+                name: workspace.name,
+                num: workspace.num,
+                output: workspace.output,
+                focused: workspace.focused,
+                visible: workspace.visible,
+                urgent: workspace.urgent,
+                windows: windows_by_workspace
+                    .remove(&workspace.id)
+                    .unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    /// Waits for an event that changes the workspaces or their windows. Any of these can change
+    /// which workspaces are visible or what is on them, so all of them are redrawn after it
+    async fn wait_for_change(&mut self) -> anyhow::Result<()> {
+        loop {
+            match self.sway.next_event().await {
+                SwayMessage::Event(event) => {
+                    if changes_workspaces(&event) {
+                        return Ok(());
+                    }
+                }
+                SwayMessage::Reconnected => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Sway sends no workspace event when a window opens, closes or moves to another workspace, so
+/// those window events are needed too. Other window events, like title changes, are skipped
+fn changes_workspaces(event: &Event) -> bool {
+    match event {
+        Event::Workspace(_) | Event::Output(_) => true,
+        Event::Window(window_event) => matches!(
+            window_event.change,
+            WindowChange::New | WindowChange::Close | WindowChange::Move
+        ),
+        _ => false,
+    }
+}
+
+/// i3 is at version 4, while Sway's versions start at 1. Sway gives its own version, not the
+/// version of i3 it is compatible with
+fn is_i3(version: &Version) -> bool {
+    version.major >= 4
+}
+
+/// Finds every workspace in the tree and the app ids of all windows in them
+fn collect_windows_by_workspace(
+    node: &Node,
+    windows_by_workspace: &mut HashMap<i64, Vec<Option<String>>>,
+) {
+    if node.node_type == NodeType::Workspace {
+        let mut windows = Vec::new();
+        collect_windows(node, &mut windows);
+        windows_by_workspace.insert(node.id, windows);
+        return;
+    }
+    for child in &node.nodes {
+        collect_windows_by_workspace(child, windows_by_workspace);
+    }
+}
+
+/// Windows are the leaves of the tree. Containers only hold other containers or windows. X11
+/// windows, which are all of them on i3 and the XWayland ones on Sway, have no app id, so their
+/// window class is used instead
+fn collect_windows(node: &Node, windows: &mut Vec<Option<String>>) {
+    for child in node.nodes.iter().chain(node.floating_nodes.iter()) {
+        if child.nodes.is_empty() && child.floating_nodes.is_empty() {
+            windows.push(child.app_id.clone().or_else(|| {
+                child
+                    .window_properties
+                    .as_ref()
+                    .and_then(|window_properties| window_properties.class.clone())
+            }));
+        } else {
+            collect_windows(child, windows);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::{json, Value};
+    use swayipc_async::{Event, Node, WindowEvent};
+
+    use super::super::{window_count_fraction, WorkspaceState, WorkspacesModuleOptions};
+    use super::{changes_workspaces, collect_windows_by_workspace, is_i3};
+
+    /// A node of `get_tree` with only the fields swayipc needs
+    fn node(id: i64, node_type: &str, nodes: Vec<Value>, floating_nodes: Vec<Value>) -> Value {
+        let rect = json!({"x": 0, "y": 0, "width": 100, "height": 100});
+        json!({
+            "id": id, "name": null, "type": node_type, "border": "none",
+            "current_border_width": 0, "layout": "splith", "percent": null,
+            "rect": rect, "window_rect": rect, "deco_rect": rect, "geometry": rect,
+            "urgent": false, "focused": false, "focus": [], "sticky": false,
+            "nodes": nodes, "floating_nodes": floating_nodes,
+        })
+    }
+
+    /// A window with `extra` fields, like its app id
+    fn window(id: i64, extra: Value) -> Value {
+        let mut window = node(id, "con", vec![], vec![]);
+        window
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        window
+    }
+
+    /// An output with a workspace that has a split container and a floating window, and an
+    /// empty workspace
+    fn tree(windows: [Value; 3]) -> Node {
+        let [first, second, floating] = windows;
+        let split = node(11, "con", vec![first, second], vec![]);
+        let workspace = node(10, "workspace", vec![split], vec![floating]);
+        let empty = node(20, "workspace", vec![], vec![]);
+        let output = node(2, "output", vec![workspace, empty], vec![]);
+        serde_json::from_value(node(1, "root", vec![output], vec![])).unwrap()
+    }
+
+    #[test]
+    fn test_collect_windows() {
+        let tree = tree([
+            window(12, json!({"app_id": "firefox"})),
+            window(13, json!({"app_id": "foot"})),
+            window(14, json!({"app_id": "mpv"})),
+        ]);
+        let mut windows_by_workspace = HashMap::new();
+        collect_windows_by_workspace(&tree, &mut windows_by_workspace);
+        let app_id = |app_id: &str| Some(app_id.to_owned());
+        assert_eq!(
+            windows_by_workspace,
+            HashMap::from([
+                (10, vec![app_id("firefox"), app_id("foot"), app_id("mpv")]),
+                (20, vec![]),
+            ])
+        );
+
+        let workspace = WorkspaceState {
+            id: 10,
+            name: "1".to_owned(),
+            num: 1,
+            output: "DP-1".to_owned(),
+            focused: false,
+            visible: false,
+            urgent: false,
+            windows: windows_by_workspace.remove(&10).unwrap(),
+        };
+        let options = WorkspacesModuleOptions::default();
+        assert_eq!(window_count_fraction(&workspace, &options), 0.75);
+    }
+
+    fn window_event(change: &str) -> Event {
+        let rect = r#"{"x": 0, "y": 0, "width": 100, "height": 100}"#;
+        let json = format!(
+            r#"{{
+                "change": "{change}",
+                "container": {{
+                    "id": 5, "name": "foot", "type": "con", "border": "none",
+                    "current_border_width": 0, "layout": "none", "percent": 1.0,
+                    "rect": {rect}, "window_rect": {rect}, "deco_rect": {rect},
+                    "geometry": {rect}, "urgent": false, "focused": true, "focus": [],
+                    "nodes": [], "floating_nodes": [], "sticky": false, "app_id": "foot"
+                }}
+            }}"#
+        );
+        Event::Window(Box::new(
+            serde_json::from_str::<WindowEvent>(&json).unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_collect_i3_windows() {
+        // i3 has no app ids. Windows without a class, which are rare, stay unknown
+        let class = |class: &str| json!({"window_properties": {"class": class, "title": "x"}});
+        let tree = tree([
+            window(12, class("firefox")),
+            window(13, class("discord")),
+            window(14, json!({"window_properties": {"title": "x"}})),
+        ]);
+        let mut windows_by_workspace = HashMap::new();
+        collect_windows_by_workspace(&tree, &mut windows_by_workspace);
+        assert_eq!(
+            windows_by_workspace[&10],
+            vec![Some("firefox".to_owned()), Some("discord".to_owned()), None]
+        );
+    }
+
+    #[test]
+    fn test_is_i3() {
+        let version = |major, human_readable| {
+            serde_json::from_value(json!({
+                "major": major, "minor": 0, "patch": 0,
+                "human_readable": human_readable, "loaded_config_file_name": "config",
+            }))
+            .unwrap()
+        };
+        assert!(is_i3(&version(4, "4.23 (2023-10-29)")));
+        assert!(!is_i3(&version(1, "1.9")));
+    }
+
+    #[test]
+    fn test_window_events() {
+        assert!(changes_workspaces(&window_event("new")));
+        assert!(changes_workspaces(&window_event("close")));
+        assert!(changes_workspaces(&window_event("move")));
+        assert!(!changes_workspaces(&window_event("title")));
+        assert!(!changes_workspaces(&window_event("focus")));
+    }
+}